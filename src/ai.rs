@@ -1,76 +1,138 @@
-use std::time::Duration;
-
-use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy::prelude::*;
 use ctrl_macros::ok_or_continue;
 
 use crate::{
-    players::{OwnedBy, Player},
-    ship::{AttachedFleet, Fleet, FlyTo},
-    star_generation::Star,
+    app_state::SimulationSet,
+    game_command::GameCommand,
+    match_settings::Difficulty,
+    players::{OwnedBy, Player, PlayerId},
+    replay::ReplayPlayback,
+    ship::{production_per_second, AttachedFleet, CombatRules, Fleet, FleetSpeedRules},
+    simulation_clock::{every_n_ticks, TICKS_PER_SECOND},
+    star_generation::{Star, StarId},
 };
 
-const EVERY_FIVE_SECONDS: f32 = 5.0;
+const EVERY_FIVE_SECONDS: u64 = 5 * TICKS_PER_SECOND;
+/// Share of its fleet an AI player sends at least, e.g. to unowned stars.
+const MIN_SEND_RATIO: f32 = 0.2;
 
 pub struct AiPlugin;
 
 impl Plugin for AiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, send_fleet.run_if(on_timer(Duration::from_secs_f32(EVERY_FIVE_SECONDS))));
+        app.add_systems(
+            FixedUpdate,
+            send_fleet
+                .run_if(every_n_ticks(EVERY_FIVE_SECONDS))
+                .run_if(not(resource_exists::<ReplayPlayback>()))
+                .in_set(SimulationSet::Ai),
+        );
     }
 }
 
 fn send_fleet(
-    q_attached_fleet: Query<(Entity, &AttachedFleet, &Transform)>,
-    q_enemy_stars: Query<(Entity, Option<&OwnedBy>, &Transform), With<Star>>,
-    mut q_fleet: Query<&mut Fleet>,
-    q_player: Query<&Player>,
-    mut commands: Commands,
+    q_attached_fleet: Query<(Entity, &StarId, &AttachedFleet, &Transform)>,
+    q_enemy_stars: Query<
+        (
+            Entity,
+            &StarId,
+            &Star,
+            Option<&OwnedBy>,
+            Option<&AttachedFleet>,
+            &Transform,
+        ),
+        With<Star>,
+    >,
+    q_fleet: Query<&Fleet>,
+    q_player: Query<(&Player, &PlayerId, Option<&Difficulty>)>,
+    combat_rules: Res<CombatRules>,
+    speed_rules: Res<FleetSpeedRules>,
+    mut ev_game_command: EventWriter<GameCommand>,
 ) {
-    for (first_entity, attached_fleet, transform) in q_attached_fleet.iter() {
-        let mut fleet = q_fleet.get_mut(attached_fleet.fleet_id).unwrap();
+    // Equally distant targets are picked in the same order every match
+    let mut enemy_stars: Vec<_> = q_enemy_stars.iter().collect();
+    enemy_stars.sort_by_key(|&(_, &star_id, ..)| star_id);
+
+    let mut own_stars: Vec<_> = q_attached_fleet.iter().collect();
+    own_stars.sort_by_key(|&(_, &star_id, ..)| star_id);
+
+    for (first_entity, &first_star_id, attached_fleet, transform) in own_stars {
+        let fleet = ok_or_continue!(q_fleet.get(attached_fleet.fleet_id));
+        let (fleet_player, fleet_size) = (fleet.player, fleet.size);
 
-        let player = ok_or_continue!(q_player.get(fleet.player));
+        let (player, &player_id, difficulty) = ok_or_continue!(q_player.get(fleet_player));
         if player.is_human {
             continue;
         }
 
-        let mut closest_distance = f32::MAX;
-        let mut selected_enemy = None;
+        let difficulty = difficulty.copied().unwrap_or_default();
 
-        for (enemy, other_star, other_transfrorm) in q_enemy_stars.iter() {
+        // Big fleets are slow, so the target reached first is often not the closest one but
+        // one that takes fewer ships
+        let mut selected_enemy = None;
+        for &(enemy, &id, star, other_star, other_fleet, other_transform) in enemy_stars.iter() {
             if first_entity == enemy {
                 continue;
             }
             if let Some(other_star) = other_star {
-                if fleet.player == other_star.player {
+                if fleet_player == other_star.player {
                     continue;
                 }
             }
 
-            let dx = transform.translation.x - other_transfrorm.translation.x;
-            let dy = transform.translation.y - other_transfrorm.translation.y;
-            let distance_squared = dx * dx + dy * dy;
-            if distance_squared < closest_distance {
-                closest_distance = distance_squared;
-                selected_enemy = Some(enemy);
+            let distance = transform.translation.distance(other_transform.translation);
+            // Defenders when the fleet arrives, counting what the star produces meanwhile. The
+            // whole fleet is the slowest that can be sent, so enough ships are sent either way.
+            let defence = other_fleet
+                .and_then(|other_fleet| q_fleet.get(other_fleet.fleet_id).ok())
+                .map_or(0.0, |other_fleet| {
+                    let travel_seconds = speed_rules.travel_seconds(fleet_size, distance);
+                    (other_fleet.size + production_per_second(star.size) * travel_seconds)
+                        * combat_rules.defender_strength(star.size)
+                });
+            let Some(ratio) = send_ratio(difficulty, fleet_size, defence) else {
+                continue;
+            };
+
+            let arrival_seconds = speed_rules.travel_seconds(fleet_size * ratio, distance);
+            if selected_enemy.map_or(true, |(_, _, seconds)| arrival_seconds < seconds) {
+                selected_enemy = Some((id, ratio, arrival_seconds));
             }
         }
 
-        if let Some(selected_enemy) = selected_enemy {
-            let send_fleet_size = fleet.size * 0.5;
-            fleet.size -= send_fleet_size;
-
-            commands
-                .spawn_empty()
-                .insert(Fleet {
-                    player: fleet.player,
-                    size: send_fleet_size,
-                })
-                .insert(FlyTo {
-                    origin_star: first_entity,
-                    destination_star: selected_enemy,
-                });
-            // info!("Send fleet size: {send_fleet_size} {closest_distance}");
-        }
+        let Some((selected_enemy, ratio, _)) = selected_enemy else {
+            continue;
+        };
+
+        let send_fleet_size = fleet_size * ratio;
+        ev_game_command.send(GameCommand::SendFleet {
+            player: player_id,
+            from: first_star_id,
+            to: selected_enemy,
+            amount: send_fleet_size,
+        });
+        // info!("Send fleet size: {send_fleet_size}");
+    }
+}
+
+/// Share of its fleet an AI player sends at a target with the given defence, enough to beat it
+/// with a margin. Only easy players attack when they can't spare that many ships.
+fn send_ratio(difficulty: Difficulty, fleet_size: f32, defence: f32) -> Option<f32> {
+    let (margin, max_ratio) = match difficulty {
+        Difficulty::Easy => (1.0, 0.5),
+        Difficulty::Normal => (1.2, 0.7),
+        Difficulty::Hard => (1.5, 0.9),
+    };
+    if fleet_size <= 0.0 {
+        return None;
+    }
+
+    let ratio = (defence * margin / fleet_size).max(MIN_SEND_RATIO);
+    if ratio <= max_ratio {
+        Some(ratio)
+    } else if difficulty == Difficulty::Easy {
+        Some(max_ratio)
+    } else {
+        None
     }
 }
@@ -0,0 +1,69 @@
+use bevy::prelude::*;
+
+#[derive(States, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+pub enum AppState {
+    #[default]
+    MainMenu,
+    MatchSetup,
+    Playing,
+    Paused,
+    GameOver,
+}
+
+/// Simulation systems, which only run while a match is being played.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GameplaySet;
+
+/// Steps of a single simulation tick in `FixedUpdate`, run in this order.
+#[derive(SystemSet, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SimulationSet {
+    Setup,
+    Commands,
+    Production,
+    Ai,
+    Movement,
+    Combat,
+    Outcome,
+}
+
+/// Spawned for a single match and despawned when the match is torn down.
+#[derive(Component)]
+pub struct MatchEntity;
+
+#[derive(Resource, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MatchResult {
+    Victory,
+    Defeat,
+}
+
+pub struct AppStatePlugin;
+
+impl Plugin for AppStatePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<AppState>()
+            .configure_set(Update, GameplaySet.run_if(in_state(AppState::Playing)))
+            .configure_set(FixedUpdate, GameplaySet.run_if(in_state(AppState::Playing)))
+            .configure_sets(
+                FixedUpdate,
+                (
+                    SimulationSet::Setup,
+                    SimulationSet::Commands,
+                    SimulationSet::Production,
+                    SimulationSet::Ai,
+                    SimulationSet::Movement,
+                    SimulationSet::Combat,
+                    SimulationSet::Outcome,
+                )
+                    .chain()
+                    .in_set(GameplaySet),
+            )
+            .add_systems(OnEnter(AppState::MainMenu), despawn_match_entities)
+            .add_systems(OnExit(AppState::GameOver), despawn_match_entities);
+    }
+}
+
+fn despawn_match_entities(mut commands: Commands, query: Query<Entity, With<MatchEntity>>) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+}
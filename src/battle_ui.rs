@@ -0,0 +1,161 @@
+use bevy::{prelude::*, sprite::Anchor};
+use ctrl_macros::ok_or_continue;
+
+use crate::{
+    app_state::MatchEntity,
+    players::Player,
+    ship::{Battle, CombatResolved, Fleet, FleetSpeed, FlyTo},
+};
+
+/// Real seconds the losses of a finished battle stay on screen.
+const OUTCOME_SECONDS: f32 = 2.0;
+/// Distance the losses of a finished battle float up while they fade.
+const OUTCOME_RISE: f32 = 15.0;
+const LOSSES_COLOR: Color = Color::rgb(1.0, 0.35, 0.35);
+
+/// Shows the size of fleets in flight, what they lost so far while fighting at a star, and the
+/// losses on both sides once a battle is over.
+pub struct BattleUiPlugin;
+
+/// Size of a fleet in flight next to its icon, followed by its losses while it's in a battle.
+#[derive(Component)]
+pub struct FleetSizeText;
+
+/// Losses of a finished battle, floating up from its star.
+#[derive(Component)]
+pub struct BattleOutcome {
+    start_y: f32,
+    expires_at: f32,
+}
+
+impl Plugin for BattleUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (add_fleet_size_text, update_fleet_size_text))
+            .add_systems(Update, (show_battle_outcomes, fade_battle_outcomes));
+    }
+}
+
+fn text_style(asset_server: &AssetServer, color: Color) -> TextStyle {
+    TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 40.0,
+        color,
+    }
+}
+
+/// Fleets get their label once they're placed at their origin star.
+fn add_fleet_size_text(
+    query: Query<Entity, (With<FlyTo>, Added<FleetSpeed>)>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    for entity in query.iter() {
+        let label = commands
+            .spawn(Text2dBundle {
+                text: Text::from_sections([
+                    TextSection::new("", text_style(&asset_server, Color::WHITE)),
+                    TextSection::new("", text_style(&asset_server, LOSSES_COLOR)),
+                ]),
+                text_anchor: Anchor::CenterLeft,
+                transform: Transform::from_xyz(8.0, 0.0, 1.0).with_scale(Vec3::new(0.2, 0.2, 0.2)),
+                ..default()
+            })
+            .insert(FleetSizeText)
+            .id();
+
+        commands.entity(entity).add_child(label);
+    }
+}
+
+fn update_fleet_size_text(
+    q_fleet: Query<(&Fleet, Option<&Battle>, &Children), With<FlyTo>>,
+    mut q_fleet_size_text: Query<&mut Text, With<FleetSizeText>>,
+) {
+    for (fleet, battle, children) in q_fleet.iter() {
+        for &child in children.iter() {
+            let mut text = ok_or_continue!(q_fleet_size_text.get_mut(child));
+            text.sections[0].value = format!("{:.1}", fleet.size);
+            text.sections[1].value = battle.map_or_else(String::new, |battle| {
+                format!(" -{:.1}", battle.attacker_losses)
+            });
+        }
+    }
+}
+
+/// Shows the ships the attacker and the defender lost, each in their colour.
+fn show_battle_outcomes(
+    mut ev_combat_resolved: EventReader<CombatResolved>,
+    q_star: Query<&Transform>,
+    q_player: Query<&Player>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    time: Res<Time>,
+) {
+    for resolved in ev_combat_resolved.iter() {
+        // Unowned stars without defenders are taken without a fight
+        if resolved.attacker_losses <= 0.0 && resolved.defender_losses <= 0.0 {
+            continue;
+        }
+        let star = ok_or_continue!(q_star.get(resolved.star));
+
+        let player_color = |player: Option<Entity>| {
+            player
+                .and_then(|player| q_player.get(player).ok())
+                .map_or(Color::WHITE, |player| player.color)
+        };
+        let outcome = if resolved.ownership_changed {
+            "captured"
+        } else {
+            "repelled"
+        };
+        let start_y = star.translation.y + 25.0;
+
+        commands
+            .spawn(Text2dBundle {
+                text: Text::from_sections([
+                    TextSection::new(
+                        format!("-{:.1}", resolved.attacker_losses),
+                        text_style(&asset_server, player_color(Some(resolved.attacker))),
+                    ),
+                    TextSection::new(" / ", text_style(&asset_server, Color::WHITE)),
+                    TextSection::new(
+                        format!("-{:.1}", resolved.defender_losses),
+                        text_style(&asset_server, player_color(resolved.defender)),
+                    ),
+                    TextSection::new(
+                        format!(" {outcome}"),
+                        text_style(&asset_server, Color::WHITE),
+                    ),
+                ])
+                .with_alignment(TextAlignment::Center),
+                transform: Transform::from_xyz(star.translation.x, start_y, 5.0)
+                    .with_scale(Vec3::new(0.25, 0.25, 0.25)),
+                ..default()
+            })
+            .insert(BattleOutcome {
+                start_y,
+                expires_at: time.raw_elapsed_seconds() + OUTCOME_SECONDS,
+            })
+            .insert(MatchEntity);
+    }
+}
+
+fn fade_battle_outcomes(
+    mut query: Query<(Entity, &mut Transform, &mut Text, &BattleOutcome)>,
+    mut commands: Commands,
+    time: Res<Time>,
+) {
+    for (entity, mut transform, mut text, outcome) in query.iter_mut() {
+        let remaining = outcome.expires_at - time.raw_elapsed_seconds();
+        if remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        let progress = 1.0 - remaining / OUTCOME_SECONDS;
+        transform.translation.y = outcome.start_y + OUTCOME_RISE * progress;
+        for section in text.sections.iter_mut() {
+            section.style.color.set_a(1.0 - progress);
+        }
+    }
+}
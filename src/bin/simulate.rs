@@ -0,0 +1,231 @@
+//! Runs AI-only matches without rendering, as fast as possible, and prints the results.
+//!
+//! ```sh
+//! cargo run --release --bin simulate -- --matches 10 --seed 1 --players 4 --format csv
+//! ```
+//!
+//! Options: `--matches <n>`, `--seed <first seed>` or `--seeds <a,b,c>`, `--players <n>`,
+//! `--max-ticks <n>`, `--sample-every <ticks>`, `--format json|csv`, plus the galaxy options of
+//! the game (`--preset`, `--layout`, `--map`).
+
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use bevy_jam_1::{
+    app_state::AppState,
+    launch_args::launch_arg,
+    match_settings::{MatchSettings, PlayerSettings},
+    players::{OwnedBy, Player},
+    ship::Fleet,
+    simulation_clock::{SimulationClock, TICKS_PER_SECOND},
+    star_generation::Star,
+    StarsIoSimulationPlugins,
+};
+
+const DEFAULT_PLAYERS: usize = 4;
+/// Half an hour of game time.
+const DEFAULT_MAX_TICKS: u64 = 30 * 60 * TICKS_PER_SECOND;
+const DEFAULT_SAMPLE_EVERY: u64 = 10 * TICKS_PER_SECOND;
+
+#[derive(Serialize)]
+struct MatchReport {
+    seed: u64,
+    players: Vec<String>,
+    /// `None` if nobody won before the tick limit.
+    winner: Option<String>,
+    duration_ticks: u64,
+    star_counts: Vec<StarCountSample>,
+}
+
+#[derive(Serialize)]
+struct StarCountSample {
+    tick: u64,
+    /// Stars owned by each player, in the order of `MatchReport::players`.
+    stars: Vec<usize>,
+}
+
+enum Format {
+    Json,
+    Csv,
+}
+
+fn main() {
+    let seeds = seeds();
+    let player_count = parse_arg("players").unwrap_or(DEFAULT_PLAYERS).max(2);
+    let max_ticks = parse_arg("max-ticks").unwrap_or(DEFAULT_MAX_TICKS);
+    let sample_every = parse_arg("sample-every")
+        .unwrap_or(DEFAULT_SAMPLE_EVERY)
+        .max(1);
+    let format = match launch_arg("format").as_deref() {
+        None | Some("json") => Format::Json,
+        Some("csv") => Format::Csv,
+        Some(other) => {
+            eprintln!("Unknown format {other}, expected json or csv");
+            std::process::exit(1);
+        }
+    };
+
+    let reports: Vec<_> = seeds
+        .into_iter()
+        .map(|seed| {
+            eprintln!("Simulating seed {seed}");
+            run_match(seed, player_count, max_ticks, sample_every)
+        })
+        .collect();
+
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&reports).unwrap()),
+        Format::Csv => print_csv(&reports),
+    }
+}
+
+fn parse_arg<T: std::str::FromStr>(name: &str) -> Option<T> {
+    let value = launch_arg(name)?;
+    match value.parse() {
+        Ok(value) => Some(value),
+        Err(_) => {
+            eprintln!("Invalid value for --{name}: {value}");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// `--seeds` if given, otherwise `--matches` consecutive seeds starting at `--seed`.
+fn seeds() -> Vec<u64> {
+    if let Some(seeds) = launch_arg("seeds") {
+        return seeds
+            .split(',')
+            .map(|seed| {
+                seed.trim().parse().unwrap_or_else(|_| {
+                    eprintln!("Invalid seed: {seed}");
+                    std::process::exit(1);
+                })
+            })
+            .collect();
+    }
+
+    let matches: u64 = parse_arg("matches").unwrap_or(1);
+    let first_seed = parse_arg("seed").unwrap_or_else(rand::random);
+    (0..matches)
+        .map(|index| first_seed.wrapping_add(index))
+        .collect()
+}
+
+fn run_match(seed: u64, player_count: usize, max_ticks: u64, sample_every: u64) -> MatchReport {
+    let mut settings = MatchSettings::from_launch_args();
+    settings.seed = seed;
+    settings.players = settings
+        .palette
+        .colors(player_count)
+        .into_iter()
+        .enumerate()
+        .map(|(index, color)| PlayerSettings::ai(index, color))
+        .collect();
+    let players: Vec<_> = settings
+        .players
+        .iter()
+        .map(|player| player.name.clone())
+        .collect();
+
+    let mut app = App::new();
+    app.add_plugins(MinimalPlugins)
+        .add_plugins(StarsIoSimulationPlugins)
+        .init_resource::<Input<KeyCode>>()
+        .insert_resource(settings);
+
+    // The galaxy is generated when the match starts from the setup screen
+    for state in [AppState::MatchSetup, AppState::Playing] {
+        app.world.resource_mut::<NextState<AppState>>().set(state);
+        app.update();
+    }
+
+    let mut star_counts = Vec::new();
+    let mut winner = None;
+    loop {
+        // Skip the fixed timestep and run ticks back to back
+        app.world.run_schedule(FixedUpdate);
+        let tick = app.world.resource::<SimulationClock>().tick();
+
+        let finished = tick >= max_ticks;
+        winner = winner.or_else(|| only_remaining_player(&mut app.world));
+        if tick % sample_every == 0 || finished || winner.is_some() {
+            star_counts.push(StarCountSample {
+                tick,
+                stars: count_stars(&mut app.world, &players),
+            });
+        }
+        if finished || winner.is_some() {
+            break;
+        }
+    }
+
+    MatchReport {
+        seed,
+        players,
+        winner,
+        duration_ticks: app.world.resource::<SimulationClock>().tick(),
+        star_counts,
+    }
+}
+
+/// The match is won once every star and fleet that has an owner belongs to the same player.
+fn only_remaining_player(world: &mut World) -> Option<String> {
+    let mut owners: HashSet<Entity> = world
+        .query_filtered::<&OwnedBy, With<Star>>()
+        .iter(world)
+        .map(|owned_by| owned_by.player)
+        .collect();
+    owners.extend(
+        world
+            .query::<&Fleet>()
+            .iter(world)
+            .map(|fleet| fleet.player),
+    );
+
+    if owners.len() != 1 {
+        return None;
+    }
+    let &winner = owners.iter().next()?;
+    let player = world.get::<Player>(winner)?;
+    Some(player.name.clone())
+}
+
+fn count_stars(world: &mut World, players: &[String]) -> Vec<usize> {
+    let mut counts = vec![0; players.len()];
+    let mut q_owned_star = world.query_filtered::<&OwnedBy, With<Star>>();
+    for owned_by in q_owned_star.iter(world) {
+        let Some(player) = world.get::<Player>(owned_by.player) else {
+            continue;
+        };
+        if let Some(index) = players.iter().position(|name| *name == player.name) {
+            counts[index] += 1;
+        }
+    }
+    counts
+}
+
+/// One row per star count sample, with a column for each player's stars.
+fn print_csv(reports: &[MatchReport]) {
+    let Some(first) = reports.first() else {
+        return;
+    };
+    println!(
+        "seed,winner,duration_ticks,tick,{}",
+        first.players.join(",")
+    );
+    for report in reports {
+        for sample in report.star_counts.iter() {
+            let stars: Vec<_> = sample.stars.iter().map(|stars| stars.to_string()).collect();
+            println!(
+                "{},{},{},{},{}",
+                report.seed,
+                report.winner.as_deref().unwrap_or(""),
+                report.duration_ticks,
+                sample.tick,
+                stars.join(",")
+            );
+        }
+    }
+}
@@ -2,7 +2,9 @@ use bevy::prelude::*;
 use ctrl_macros::ok_or_continue;
 
 use crate::{
+    app_state::AppState,
     players::{OwnedBy, Player},
+    replay::ReplayPlayback,
     top_down_camera::{TopDownCamera, TopDownCameraPlugin},
 };
 
@@ -16,6 +18,12 @@ impl Plugin for CameraPlugin {
         app.add_systems(Startup, setup_camera)
             .insert_resource(ZoomedIn(false))
             .add_systems(Update, zoom_camera_to_player)
+            .add_systems(OnEnter(AppState::MainMenu), reset_zoomed_in)
+            // Starting a replay over keeps the camera where it is
+            .add_systems(
+                OnExit(AppState::GameOver),
+                reset_zoomed_in.run_if(not(resource_exists::<ReplayPlayback>())),
+            )
             .add_plugins(TopDownCameraPlugin);
     }
 }
@@ -32,6 +40,10 @@ fn setup_camera(mut commands: Commands) {
     });
 }
 
+fn reset_zoomed_in(mut zoomed_in: ResMut<ZoomedIn>) {
+    zoomed_in.0 = false;
+}
+
 fn zoom_camera_to_player(
     q_player_star_added: Query<(&Transform, &OwnedBy), (Added<OwnedBy>, Without<TopDownCamera>)>,
     q_player: Query<&Player>,
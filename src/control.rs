@@ -1,19 +1,89 @@
 use bevy::prelude::*;
-use ctrl_macros::{ok_or_continue, some_or_return};
+use ctrl_macros::ok_or_continue;
 
 use crate::{
-    players::{OwnedBy, Player},
+    app_state::GameplaySet,
+    game_command::GameCommand,
+    players::{OwnedBy, Player, PlayerId},
+    replay::ReplayPlayback,
     selection::OnSelected,
     selection_ui::Selected,
-    ship::{AttachedFleet, Fleet, FlyTo},
+    ship::{AttachedFleet, CombatRules, Fleet, FleetId, FlyTo},
+    star_generation::{Star, StarId},
+    target_assignment::{assign_targets, OrderSource, OrderTarget, TargetingMode},
     // top_down_camera::{screen_to_world, TopDownCamera},
 };
 
+/// Share of each fleet, from 1 to 4 quarters, picked with the number keys.
+const SEND_FRACTION_KEYS: [KeyCode; 4] =
+    [KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4];
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Key0,
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+];
+
 // struct SelectedSingle {
 //     fleet: Option<Entity>,
 //     star: Option<Entity>,
 // }
 
+/// How many ships the human player sends from each selected star.
+#[derive(Resource, Clone, Copy, Debug, PartialEq)]
+pub enum SendAmount {
+    /// Share of the star's fleet.
+    Fraction(f32),
+    /// Exact number of ships, or all of them if the star has fewer.
+    Exact(u32),
+}
+
+impl Default for SendAmount {
+    fn default() -> Self {
+        SendAmount::Fraction(0.5)
+    }
+}
+
+impl SendAmount {
+    /// Ships to send out of a fleet of the given size.
+    pub fn ships(self, fleet_size: f32) -> f32 {
+        match self {
+            SendAmount::Fraction(fraction) => fleet_size * fraction,
+            SendAmount::Exact(ships) => (ships as f32).min(fleet_size),
+        }
+    }
+}
+
+/// What ships sent at a star do when they get there.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Order {
+    /// Fight the defenders of a star owned by another player or nobody.
+    Attack,
+    /// Join the fleet of one of the player's own stars.
+    Reinforce,
+}
+
+impl Order {
+    /// The order for ships of `player` sent at a star owned by `star_owner`.
+    pub fn at(player: Entity, star_owner: Option<Entity>) -> Self {
+        if star_owner == Some(player) {
+            Order::Reinforce
+        } else {
+            Order::Attack
+        }
+    }
+}
+
+/// Whether the exact number of ships to send is being typed in.
+#[derive(Resource, Default)]
+pub struct TypingSendAmount(pub bool);
+
 pub struct ControlPlugin;
 
 impl Plugin for ControlPlugin {
@@ -24,7 +94,26 @@ impl Plugin for ControlPlugin {
         // })
         // .add_systems(Update, mouse_select)
         // .add_systems(Update, mouse_send)
-        app.add_systems(Update, attack_selection);
+        app.add_event::<OnSelected>()
+            .init_resource::<SendAmount>()
+            .init_resource::<TypingSendAmount>()
+            .init_resource::<TargetingMode>()
+            .add_systems(
+                Update,
+                (send_amount_hotkeys, targeting_mode_hotkeys).in_set(GameplaySet),
+            )
+            // Replays are re-simulated from the recorded orders only
+            .add_systems(
+                Update,
+                (
+                    order_selection,
+                    set_rally_points,
+                    redirect_selected_fleets,
+                    recall_selected_fleets,
+                )
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .in_set(GameplaySet),
+            );
     }
 }
 
@@ -150,74 +239,313 @@ fn in_sprite(world_pos: Vec2, sprite: &Sprite, transform: &GlobalTransform) -> b
 
 */
 
-fn attack_selection(
+/// Right-clicking stars sends ships from the selected stars to them. Other players' and unowned
+/// stars get attacked, the player's own stars reinforced.
+fn order_selection(
     mut ev_selected: EventReader<OnSelected>,
-
     q_selected: Query<&Parent, With<Selected>>,
-    q_attached_fleet: Query<(Option<&OwnedBy>, Option<&AttachedFleet>, Entity)>,
-    mut q_fleet: Query<&mut Fleet>,
-    q_player: Query<&Player>,
-
-    mut commands: Commands,
+    q_star: Query<(
+        &StarId,
+        &Star,
+        &Transform,
+        Option<&OwnedBy>,
+        Option<&AttachedFleet>,
+    )>,
+    q_fleet: Query<&Fleet>,
+    q_player: Query<(&Player, &PlayerId)>,
+    send_amount: Res<SendAmount>,
+    targeting_mode: Res<TargetingMode>,
+    combat_rules: Res<CombatRules>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut ev_game_command: EventWriter<GameCommand>,
 ) {
     for event in ev_selected.iter() {
-        if event.mouse_button != MouseButton::Right {
+        if event.mouse_button != MouseButton::Right || shift_held(&keyboard_input) {
             continue;
         }
 
-        let my_fleets_stars: Vec<_> = q_selected
+        let mut my_fleets_stars: Vec<_> = q_selected
             .iter()
             .filter_map(|my_entity| {
-                let (owned_by, attached_fleet, entity) =
-                    q_attached_fleet.get(my_entity.get()).ok()?;
+                let entity = my_entity.get();
+                let (&star_id, _, transform, owned_by, attached_fleet) = q_star.get(entity).ok()?;
                 let owned_by = owned_by?;
                 let attached_fleet = attached_fleet?;
 
-                let player = q_player.get(owned_by.player).ok()?;
-                if !player.is_human {
-                    return None;
-                }
+                let player_id = human_player_id(owned_by.player, &q_player)?;
 
-                // let mut fleet = q_fleet.get_mut(attached_fleet.fleet_id).ok()?;
-                // let fleet = q_fleet.get_mut(attached_fleet.fleet_id).ok()?;
+                let fleet = q_fleet.get(attached_fleet.fleet_id).ok()?;
+                let source = OrderSource {
+                    star: entity,
+                    position: transform.translation.truncate(),
+                    ships: send_amount.ships(fleet.size),
+                };
+                Some((star_id, source, owned_by.player, player_id))
+            })
+            .collect();
+        my_fleets_stars.sort_by_key(|&(star_id, ..)| star_id);
+        let Some(&(_, _, player, player_id)) = my_fleets_stars.first() else {
+            continue;
+        };
 
-                Some((attached_fleet.fleet_id, entity))
+        let mut orders: Vec<_> = event
+            .entities
+            .iter()
+            .filter_map(|&target_entity| {
+                let (&star_id, star, transform, owned_by, attached_fleet) =
+                    q_star.get(target_entity).ok()?;
+                let order = Order::at(player, owned_by.map(|owned_by| owned_by.player));
+                let fleet_size = attached_fleet
+                    .and_then(|attached_fleet| q_fleet.get(attached_fleet.fleet_id).ok())
+                    .map_or(0.0, |fleet| fleet.size);
+                let defence = match order {
+                    Order::Attack => fleet_size * combat_rules.defender_strength(star.size),
+                    Order::Reinforce => fleet_size,
+                };
+                let target = OrderTarget {
+                    star: target_entity,
+                    position: transform.translation.truncate(),
+                    defence,
+                };
+                Some((star_id, target, order))
             })
             .collect();
+        orders.sort_by_key(|&(star_id, ..)| star_id);
+        // Attacking takes priority when both own and other stars were right-clicked
+        let order = if orders.iter().any(|&(_, _, order)| order == Order::Attack) {
+            Order::Attack
+        } else {
+            Order::Reinforce
+        };
+
+        let sources: Vec<_> = my_fleets_stars
+            .into_iter()
+            .map(|(_, source, ..)| source)
+            .collect();
+        let targets: Vec<_> = orders
+            .into_iter()
+            .filter(|&(_, _, target_order)| target_order == order)
+            .map(|(_, target, _)| target)
+            .collect();
 
-        let target_stars = event.entities.iter().filter_map(|&target_entity| {
-            let (owned_by, _, star) = q_attached_fleet.get(target_entity).ok()?;
-            let owned_by = some_or_return!(owned_by, Some(star));
+        for dispatch in assign_targets(*targeting_mode, &sources, &targets) {
+            let (&from, ..) = ok_or_continue!(q_star.get(dispatch.from));
+            let (&to, ..) = ok_or_continue!(q_star.get(dispatch.to));
+            if dispatch.ships <= 0.0 {
+                continue;
+            }
+
+            ev_game_command.send(GameCommand::SendFleet {
+                player: player_id,
+                from,
+                to,
+                amount: dispatch.ships,
+            });
+        }
+    }
+}
+
+/// Shift+right-clicking one of the player's stars makes it the rally point of the selected stars.
+/// Shift+right-clicking a selected star clears its rally point.
+fn set_rally_points(
+    mut ev_selected: EventReader<OnSelected>,
+    keyboard_input: Res<Input<KeyCode>>,
+    q_selected: Query<&Parent, With<Selected>>,
+    q_star: Query<(&StarId, &Transform, &OwnedBy)>,
+    q_player: Query<(&Player, &PlayerId)>,
+    mut ev_game_command: EventWriter<GameCommand>,
+) {
+    for event in ev_selected.iter() {
+        if event.mouse_button != MouseButton::Right || !shift_held(&keyboard_input) {
+            continue;
+        }
 
-            let player = q_player.get(owned_by.player).ok()?;
-            if player.is_human {
-                return None;
+        let mut selected_stars: Vec<_> = q_selected
+            .iter()
+            .filter_map(|parent| {
+                let (&star_id, transform, owned_by) = q_star.get(parent.get()).ok()?;
+                let player_id = human_player_id(owned_by.player, &q_player)?;
+                Some((star_id, transform.translation, owned_by.player, player_id))
+            })
+            .collect();
+        selected_stars.sort_by_key(|&(star_id, ..)| star_id);
+
+        for (star, position, player, player_id) in selected_stars {
+            let clicked_own_stars = event.entities.iter().filter_map(|&entity| {
+                let (&star_id, transform, owned_by) = q_star.get(entity).ok()?;
+                (owned_by.player == player).then_some((star_id, transform.translation))
+            });
+
+            let mut rally = None;
+            let mut clears = false;
+            for (star_id, translation) in clicked_own_stars {
+                if star_id == star {
+                    clears = true;
+                    continue;
+                }
+                let distance = translation.distance(position);
+                if rally.map_or(true, |(_, nearest)| distance < nearest) {
+                    rally = Some((star_id, distance));
+                }
             }
 
-            Some(star)
-        });
+            let rally = match rally {
+                Some((rally, _)) => Some(rally),
+                None if clears => None,
+                None => continue,
+            };
+            ev_game_command.send(GameCommand::SetRally {
+                player: player_id,
+                star,
+                rally,
+            });
+        }
+    }
+}
+
+/// 1-4 send a quarter to all of each fleet. X starts typing an exact number of ships, ended with
+/// X or Enter.
+fn send_amount_hotkeys(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut send_amount: ResMut<SendAmount>,
+    mut typing: ResMut<TypingSendAmount>,
+) {
+    if typing.0 {
+        let SendAmount::Exact(mut ships) = *send_amount else {
+            typing.0 = false;
+            return;
+        };
+        for (digit, &key) in DIGIT_KEYS.iter().enumerate() {
+            if keyboard_input.just_pressed(key) {
+                ships = ships.saturating_mul(10).saturating_add(digit as u32);
+            }
+        }
+        if keyboard_input.just_pressed(KeyCode::Back) {
+            ships /= 10;
+        }
+        if *send_amount != SendAmount::Exact(ships) {
+            *send_amount = SendAmount::Exact(ships);
+        }
+        if keyboard_input.any_just_pressed([KeyCode::X, KeyCode::Return]) {
+            typing.0 = false;
+        }
+        return;
+    }
+
+    for (quarters, &key) in SEND_FRACTION_KEYS.iter().enumerate() {
+        if keyboard_input.just_pressed(key) {
+            *send_amount = SendAmount::Fraction((quarters + 1) as f32 * 0.25);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::X) {
+        *send_amount = SendAmount::Exact(0);
+        typing.0 = true;
+    }
+}
+
+/// T switches between the ways ships are spread over several targets.
+fn targeting_mode_hotkeys(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut targeting_mode: ResMut<TargetingMode>,
+) {
+    if keyboard_input.just_pressed(KeyCode::T) {
+        *targeting_mode = targeting_mode.next();
+    }
+}
+
+fn shift_held(keyboard_input: &Input<KeyCode>) -> bool {
+    keyboard_input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+}
+
+/// The [`PlayerId`] of `player` if it's the human player.
+fn human_player_id(player: Entity, q_player: &Query<(&Player, &PlayerId)>) -> Option<PlayerId> {
+    let (player, &player_id) = q_player.get(player).ok()?;
+    player.is_human.then_some(player_id)
+}
+
+/// Right-clicking stars sends the selected fleets in flight to the nearest of them, starting from
+/// where the fleets are now.
+fn redirect_selected_fleets(
+    mut ev_selected: EventReader<OnSelected>,
+    keyboard_input: Res<Input<KeyCode>>,
+    q_selected: Query<&Parent, With<Selected>>,
+    q_fly_to: Query<(&Fleet, &FleetId, &FlyTo, &Transform)>,
+    q_star: Query<(&StarId, &Transform), (With<Star>, Without<FlyTo>)>,
+    q_player: Query<(&Player, &PlayerId)>,
+    mut ev_game_command: EventWriter<GameCommand>,
+) {
+    for event in ev_selected.iter() {
+        if event.mouse_button != MouseButton::Right || shift_held(&keyboard_input) {
+            continue;
+        }
 
-        // info!("Our stars: {}", my_fleets_stars.count());
-        // info!("Target stars: {}", target_stars.count());
-
-        for ((fleet_id, star_entity), target_star) in
-            my_fleets_stars.iter().zip(target_stars.cycle())
-        {
-            let mut my_fleet = ok_or_continue!(q_fleet.get_mut(*fleet_id));
-
-            let send_fleet_size = my_fleet.size * 0.5;
-            my_fleet.size -= send_fleet_size;
-
-            commands
-                .spawn_empty()
-                .insert(Fleet {
-                    player: my_fleet.player,
-                    size: send_fleet_size,
-                })
-                .insert(FlyTo {
-                    origin_star: *star_entity,
-                    destination_star: target_star,
+        let target_stars: Vec<_> = event
+            .entities
+            .iter()
+            .filter_map(|&entity| {
+                let (&star_id, transform) = q_star.get(entity).ok()?;
+                Some((entity, star_id, transform.translation))
+            })
+            .collect();
+
+        let mut selected_fleets: Vec<_> = q_selected
+            .iter()
+            .filter_map(|parent| q_fly_to.get(parent.get()).ok())
+            .collect();
+        selected_fleets.sort_by_key(|&(_, &fleet_id, ..)| fleet_id);
+
+        for (fleet, &fleet_id, fly_to, transform) in selected_fleets {
+            let Some(player_id) = human_player_id(fleet.player, &q_player) else {
+                continue;
+            };
+
+            let nearest = target_stars.iter().min_by(|(.., a), (.., b)| {
+                a.distance(transform.translation)
+                    .total_cmp(&b.distance(transform.translation))
+            });
+            let Some(&(target_star, star_id, _)) = nearest else {
+                continue;
+            };
+            if fly_to.destination_star != target_star {
+                ev_game_command.send(GameCommand::Redirect {
+                    player: player_id,
+                    fleet: fleet_id,
+                    to: star_id,
                 });
+            }
         }
     }
 }
+
+/// R sends the selected fleets in flight back to the star they were launched from.
+fn recall_selected_fleets(
+    keyboard_input: Res<Input<KeyCode>>,
+    q_selected: Query<&Parent, With<Selected>>,
+    q_fly_to: Query<(&Fleet, &FleetId, &FlyTo)>,
+    q_player: Query<(&Player, &PlayerId)>,
+    mut ev_game_command: EventWriter<GameCommand>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::R) {
+        return;
+    }
+
+    let mut selected_fleets: Vec<_> = q_selected
+        .iter()
+        .filter_map(|parent| q_fly_to.get(parent.get()).ok())
+        .collect();
+    selected_fleets.sort_by_key(|&(_, &fleet_id, _)| fleet_id);
+
+    for (fleet, &fleet_id, fly_to) in selected_fleets {
+        let Some(player_id) = human_player_id(fleet.player, &q_player) else {
+            continue;
+        };
+        if fly_to.destination_star == fly_to.origin_star {
+            continue;
+        }
+
+        ev_game_command.send(GameCommand::Recall {
+            player: player_id,
+            fleet: fleet_id,
+        });
+    }
+}
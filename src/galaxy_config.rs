@@ -0,0 +1,157 @@
+use std::{
+    f32::consts::PI,
+    ops::{Range, RangeInclusive},
+    str::FromStr,
+};
+
+use bevy::prelude::*;
+use serde::{de::Error, Deserialize, Deserializer, Serialize};
+
+use crate::{galaxy_layout::GalaxyLayoutKind, launch_args::launch_arg};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GalaxyPreset {
+    SmallDuel,
+    Standard,
+    Huge,
+}
+
+impl FromStr for GalaxyPreset {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "small-duel" => Ok(GalaxyPreset::SmallDuel),
+            "standard" => Ok(GalaxyPreset::Standard),
+            "huge" => Ok(GalaxyPreset::Huge),
+            _ => Err(format!("Unknown galaxy preset: {s}")),
+        }
+    }
+}
+
+impl GalaxyPreset {
+    pub const ALL: [GalaxyPreset; 3] = [
+        GalaxyPreset::SmallDuel,
+        GalaxyPreset::Standard,
+        GalaxyPreset::Huge,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            GalaxyPreset::SmallDuel => "Small duel",
+            GalaxyPreset::Standard => "Standard",
+            GalaxyPreset::Huge => "Huge",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        let index = GalaxyPreset::ALL
+            .iter()
+            .position(|&preset| preset == self)
+            .unwrap_or_default();
+        GalaxyPreset::ALL[(index + 1) % GalaxyPreset::ALL.len()]
+    }
+
+    /// Uses the `preset` launch argument if given, otherwise the standard preset.
+    pub fn from_launch_args() -> Self {
+        parse_launch_arg("preset").unwrap_or(GalaxyPreset::Standard)
+    }
+}
+
+/// Shape of the generated galaxy, part of the `MatchSettings` that `StarGenerationPlugin`
+/// generates the galaxy from when a match starts.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct GalaxyConfig {
+    pub layout: GalaxyLayoutKind,
+    pub band_count: u32,
+    pub band_size: RangeInclusive<f32>,
+    pub empty_area_size: Range<f32>,
+    /// Clusters per band, multiplied by the band's (1-based) index and 2π. One value per band
+    /// from the center out, bands past the end use the last one and an empty list uses 1.
+    pub cluster_density: Vec<f32>,
+    pub stars_in_cluster: RangeInclusive<u32>,
+    pub star_size_mean: f32,
+    /// Never negative, configs that have one fail to load.
+    #[serde(deserialize_with = "deserialize_deviation")]
+    pub star_size_deviation: f32,
+    pub star_size: RangeInclusive<f32>,
+}
+
+impl GalaxyConfig {
+    pub fn from_preset(preset: GalaxyPreset) -> Self {
+        let standard = GalaxyConfig {
+            layout: GalaxyLayoutKind::Bands,
+            band_count: 5,
+            band_size: 170.0..=200.0,
+            empty_area_size: 250.0..500.0,
+            cluster_density: vec![1.0],
+            stars_in_cluster: 1..=5,
+            star_size_mean: 2.0,
+            star_size_deviation: 2.0,
+            star_size: 0.1..=10.0,
+        };
+
+        match preset {
+            GalaxyPreset::SmallDuel => GalaxyConfig {
+                band_count: 2,
+                empty_area_size: 200.0..300.0,
+                stars_in_cluster: 1..=3,
+                ..standard
+            },
+            GalaxyPreset::Standard => standard,
+            GalaxyPreset::Huge => GalaxyConfig {
+                band_count: 8,
+                cluster_density: vec![1.25],
+                ..standard
+            },
+        }
+    }
+
+    /// Uses the `preset` and `layout` launch arguments if given, otherwise the standard preset.
+    pub fn from_launch_args() -> Self {
+        let mut config = GalaxyConfig::from_preset(GalaxyPreset::from_launch_args());
+        if let Some(layout) = parse_launch_arg("layout") {
+            config.layout = layout;
+        }
+        config
+    }
+
+    /// Clusters in the band with the given (0-based) index.
+    pub fn cluster_count(&self, band: u32) -> i32 {
+        let density = self
+            .cluster_density
+            .get(band as usize)
+            .or(self.cluster_density.last())
+            .copied()
+            .unwrap_or(1.0);
+        ((band + 1) as f32 * 2.0 * PI * density) as i32
+    }
+}
+
+impl Default for GalaxyConfig {
+    fn default() -> Self {
+        GalaxyConfig::from_preset(GalaxyPreset::Standard)
+    }
+}
+
+/// A negative deviation would make the star size distribution fail to build.
+fn deserialize_deviation<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let deviation = f32::deserialize(deserializer)?;
+    if deviation.is_finite() && deviation >= 0.0 {
+        Ok(deviation)
+    } else {
+        Err(D::Error::custom(format!(
+            "Invalid star size deviation: {deviation}"
+        )))
+    }
+}
+
+fn parse_launch_arg<T: FromStr<Err = String>>(name: &str) -> Option<T> {
+    match launch_arg(name)?.parse() {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("{err}");
+            None
+        }
+    }
+}
@@ -0,0 +1,380 @@
+use std::{f32::consts::PI, str::FromStr};
+
+use bevy::prelude::*;
+use rand::{rngs::StdRng, Rng};
+use rand_distr::{Distribution, Normal};
+use serde::{Deserialize, Serialize};
+
+use crate::{galaxy_config::GalaxyConfig, galaxy_map::GalaxyMap, star_generation::NewStar};
+
+const SPIRAL_TWIST: f32 = 1.5 * PI;
+const SPIRAL_ARM_SPREAD: f32 = 0.25;
+const POISSON_PACKING: f32 = 0.7;
+const MAX_PLACEMENT_ATTEMPTS: usize = 30;
+const MAX_PLACEMENT_ROUNDS: usize = 20;
+/// How much further out from the center each round of failed placement attempts samples.
+const PLACEMENT_SPREAD_PER_ROUND: f32 = 0.1;
+
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct Band {
+    pub cluster_count: i32,
+    pub distance_from_center: f32,
+    pub size: f32,
+}
+
+pub trait GalaxyLayout {
+    fn generate(&self, config: &GalaxyConfig, rng: &mut StdRng) -> GalaxyMap;
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GalaxyLayoutKind {
+    Bands,
+    Spiral { arm_count: u32 },
+    Elliptical { aspect_ratio: f32 },
+    Scatter,
+    Rotational { symmetry: u32 },
+}
+
+impl GalaxyLayoutKind {
+    pub fn layout(&self) -> Box<dyn GalaxyLayout> {
+        match *self {
+            GalaxyLayoutKind::Bands => Box::new(BandsLayout),
+            GalaxyLayoutKind::Spiral { arm_count } => Box::new(SpiralLayout { arm_count }),
+            GalaxyLayoutKind::Elliptical { aspect_ratio } => {
+                Box::new(EllipticalLayout { aspect_ratio })
+            }
+            GalaxyLayoutKind::Scatter => Box::new(ScatterLayout),
+            GalaxyLayoutKind::Rotational { symmetry } => Box::new(RotationalLayout { symmetry }),
+        }
+    }
+}
+
+impl FromStr for GalaxyLayoutKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bands" => Ok(GalaxyLayoutKind::Bands),
+            "spiral" => Ok(GalaxyLayoutKind::Spiral { arm_count: 4 }),
+            "elliptical" => Ok(GalaxyLayoutKind::Elliptical { aspect_ratio: 0.6 }),
+            "scatter" => Ok(GalaxyLayoutKind::Scatter),
+            "rotational" => Ok(GalaxyLayoutKind::Rotational { symmetry: 2 }),
+            _ => Err(format!("Unknown galaxy layout: {s}")),
+        }
+    }
+}
+
+/// Concentric bands of clusters, evenly spaced by angle.
+pub struct BandsLayout;
+
+impl GalaxyLayout for BandsLayout {
+    fn generate(&self, config: &GalaxyConfig, rng: &mut StdRng) -> GalaxyMap {
+        let star_size_gen = star_size_gen(config);
+        let mut placer = StarPlacer::default();
+        let mut bands = Vec::new();
+
+        // Empty area between bands
+        let mut band_size_total = rng.gen_range(config.empty_area_size.clone());
+
+        for index in 0..config.band_count {
+            let size = rng.gen_range(config.band_size.clone());
+            let distance_from_center = band_size_total;
+            band_size_total += size;
+
+            let band = Band {
+                distance_from_center,
+                size,
+                cluster_count: config.cluster_count(index),
+            };
+
+            for cluster_index in 0..band.cluster_count {
+                let dist = band.distance_from_center + band.size * 0.5;
+
+                let cluster_angle = (cluster_index as f32 / band.cluster_count as f32) * PI * 2.0;
+                let cluster_x = cluster_angle.sin() * dist;
+                let cluster_y = cluster_angle.cos() * dist;
+
+                let total_stars = rng.gen_range(config.stars_in_cluster.clone());
+                for start_index in 0..total_stars {
+                    let star_size = sample_star_size(&star_size_gen, config, rng);
+                    placer.place(star_size, rng, |rng, attempt| {
+                        let star_dist = rng.gen_range(0.1..=0.5) * band.size;
+                        let mut star_angle = (start_index as f32 / total_stars as f32) * PI * 2.0;
+                        if attempt > 0 {
+                            star_angle += rng.gen_range(-PI..PI);
+                        }
+
+                        Vec2::new(
+                            cluster_x + star_angle.sin() * star_dist,
+                            cluster_y + star_angle.cos() * star_dist,
+                        )
+                    });
+                }
+            }
+
+            bands.push(band);
+
+            // Empty area between bands
+            band_size_total += rng.gen_range(config.empty_area_size.clone());
+        }
+
+        GalaxyMap {
+            bands,
+            stars: placer.into_stars(),
+        }
+    }
+}
+
+/// Stars strewn along logarithmic-looking arms that wind out of an empty core.
+pub struct SpiralLayout {
+    pub arm_count: u32,
+}
+
+impl GalaxyLayout for SpiralLayout {
+    fn generate(&self, config: &GalaxyConfig, rng: &mut StdRng) -> GalaxyMap {
+        let star_size_gen = star_size_gen(config);
+        let arm_spread = Normal::new(0.0, SPIRAL_ARM_SPREAD).unwrap();
+        let radius = galaxy_radius(config);
+        let core = range_mid(config.empty_area_size.start, config.empty_area_size.end);
+        let arm_count = self.arm_count.max(1);
+        let mut placer = StarPlacer::default();
+
+        for index in 0..star_count(config) {
+            let arm = index % arm_count;
+            let star_size = sample_star_size(&star_size_gen, config, rng);
+            placer.place(star_size, rng, |rng, _| {
+                let t: f32 = rng.gen_range(0.0..=1.0);
+                let distance = core + t * (radius - core);
+                let angle = (arm as f32 / arm_count as f32) * PI * 2.0
+                    + t * SPIRAL_TWIST
+                    + arm_spread.sample(rng);
+
+                Vec2::new(angle.sin() * distance, angle.cos() * distance)
+            });
+        }
+
+        GalaxyMap {
+            stars: placer.into_stars(),
+            ..default()
+        }
+    }
+}
+
+/// A single elliptical blob that gets denser towards the center.
+pub struct EllipticalLayout {
+    pub aspect_ratio: f32,
+}
+
+impl GalaxyLayout for EllipticalLayout {
+    fn generate(&self, config: &GalaxyConfig, rng: &mut StdRng) -> GalaxyMap {
+        let star_size_gen = star_size_gen(config);
+        let radius_x = galaxy_radius(config);
+        let radius_y = radius_x * self.aspect_ratio;
+        let x_gen = Normal::new(0.0, radius_x * 0.5).unwrap();
+        let y_gen = Normal::new(0.0, radius_y * 0.5).unwrap();
+        let mut placer = StarPlacer::default();
+
+        for _ in 0..star_count(config) {
+            let star_size = sample_star_size(&star_size_gen, config, rng);
+            placer.place(star_size, rng, |rng, _| loop {
+                let position = Vec2::new(x_gen.sample(rng), y_gen.sample(rng));
+                if (position.x / radius_x).powi(2) + (position.y / radius_y).powi(2) <= 1.0 {
+                    break position;
+                }
+            });
+        }
+
+        GalaxyMap {
+            stars: placer.into_stars(),
+            ..default()
+        }
+    }
+}
+
+/// Uniformly scattered stars kept apart with Poisson-disk (dart throwing) sampling.
+pub struct ScatterLayout;
+
+impl GalaxyLayout for ScatterLayout {
+    fn generate(&self, config: &GalaxyConfig, rng: &mut StdRng) -> GalaxyMap {
+        let star_size_gen = star_size_gen(config);
+        let radius = galaxy_radius(config);
+        let count = star_count(config);
+        let mut placer = StarPlacer::with_spacing(poisson_spacing(radius, count));
+
+        for _ in 0..count {
+            let star_size = sample_star_size(&star_size_gen, config, rng);
+            placer.place(star_size, rng, |rng, _| {
+                let distance = radius * rng.gen_range(0.0f32..=1.0).sqrt();
+                let angle = rng.gen_range(0.0..PI * 2.0);
+                Vec2::new(angle.sin() * distance, angle.cos() * distance)
+            });
+        }
+
+        GalaxyMap {
+            stars: placer.into_stars(),
+            ..default()
+        }
+    }
+}
+
+/// One scattered sector rotated around the center, so every sector (and player start) is identical.
+pub struct RotationalLayout {
+    pub symmetry: u32,
+}
+
+impl GalaxyLayout for RotationalLayout {
+    fn generate(&self, config: &GalaxyConfig, rng: &mut StdRng) -> GalaxyMap {
+        let star_size_gen = star_size_gen(config);
+        let symmetry = self.symmetry.max(1);
+        let radius = galaxy_radius(config);
+        let core = range_mid(config.empty_area_size.start, config.empty_area_size.end) * 0.5;
+        let sector_angle = PI * 2.0 / symmetry as f32;
+        let count = star_count(config);
+        let mut placer = StarPlacer::with_spacing(poisson_spacing(radius, count));
+
+        for _ in 0..count / symmetry {
+            let star_size = sample_star_size(&star_size_gen, config, rng);
+            placer.place_copies(
+                star_size,
+                rng,
+                |rng, _| {
+                    let distance = core + (radius - core) * rng.gen_range(0.0f32..=1.0).sqrt();
+                    let angle = rng.gen_range(0.0..sector_angle);
+                    Vec2::new(angle.sin() * distance, angle.cos() * distance)
+                },
+                |position| {
+                    (0..symmetry)
+                        .map(|copy| {
+                            Vec2::from_angle(-(copy as f32) * sector_angle).rotate(position)
+                        })
+                        .collect()
+                },
+            );
+        }
+
+        GalaxyMap {
+            stars: placer.into_stars(),
+            ..default()
+        }
+    }
+}
+
+/// Keeps generated stars (and their labels) from overlapping by rejecting and resampling positions.
+#[derive(Default)]
+struct StarPlacer {
+    stars: Vec<NewStar>,
+    /// Extra minimum distance between star centers, on top of not overlapping.
+    spacing: f32,
+}
+
+impl StarPlacer {
+    fn with_spacing(spacing: f32) -> Self {
+        StarPlacer {
+            spacing,
+            ..default()
+        }
+    }
+
+    /// Samples positions until the star fits. When `MAX_PLACEMENT_ATTEMPTS` samples in a row
+    /// don't fit, the samples are pushed further out from the center, so crowded galaxies grow
+    /// instead of losing stars. The sampler is also given the attempt number.
+    fn place(
+        &mut self,
+        size: f32,
+        rng: &mut StdRng,
+        sample: impl FnMut(&mut StdRng, usize) -> Vec2,
+    ) -> bool {
+        self.place_copies(size, rng, sample, |position| vec![position])
+    }
+
+    /// Like `place`, but places all `copies` of each sampled position or none of them.
+    fn place_copies(
+        &mut self,
+        size: f32,
+        rng: &mut StdRng,
+        mut sample: impl FnMut(&mut StdRng, usize) -> Vec2,
+        copies: impl Fn(Vec2) -> Vec<Vec2>,
+    ) -> bool {
+        for round in 0..MAX_PLACEMENT_ROUNDS {
+            let spread = 1.0 + round as f32 * PLACEMENT_SPREAD_PER_ROUND;
+            for attempt in 0..MAX_PLACEMENT_ATTEMPTS {
+                let position = sample(rng, round * MAX_PLACEMENT_ATTEMPTS + attempt) * spread;
+                let stars: Vec<_> = copies(position)
+                    .into_iter()
+                    .map(|position| NewStar {
+                        x: position.x,
+                        y: position.y,
+                        size,
+                        start_slot: None,
+                    })
+                    .collect();
+                // Every copy has to fit, including against the other copies of itself
+                let stars_fit = stars.iter().enumerate().all(|(index, star)| {
+                    self.fits(star)
+                        && stars[..index]
+                            .iter()
+                            .all(|other| !self.too_close(star, other))
+                });
+
+                if stars_fit {
+                    stars.into_iter().for_each(|star| self.push(star));
+                    return true;
+                }
+            }
+        }
+
+        warn!("Couldn't find room for a star of size {size}");
+        false
+    }
+
+    fn fits(&self, star: &NewStar) -> bool {
+        self.stars.iter().all(|other| !self.too_close(star, other))
+    }
+
+    fn too_close(&self, star: &NewStar, other: &NewStar) -> bool {
+        star.overlaps(other)
+            || Vec2::new(star.x, star.y).distance(Vec2::new(other.x, other.y)) < self.spacing
+    }
+
+    fn push(&mut self, star: NewStar) {
+        self.stars.push(star);
+    }
+
+    fn into_stars(self) -> Vec<NewStar> {
+        self.stars
+    }
+}
+
+fn star_size_gen(config: &GalaxyConfig) -> Normal<f32> {
+    Normal::new(config.star_size_mean, config.star_size_deviation).unwrap()
+}
+
+fn sample_star_size(star_size_gen: &Normal<f32>, config: &GalaxyConfig, rng: &mut StdRng) -> f32 {
+    star_size_gen
+        .sample(rng)
+        .clamp(*config.star_size.start(), *config.star_size.end())
+}
+
+fn range_mid(start: f32, end: f32) -> f32 {
+    (start + end) * 0.5
+}
+
+/// Outer radius of an average band layout, so every layout covers a comparable area.
+fn galaxy_radius(config: &GalaxyConfig) -> f32 {
+    let empty_area_size = range_mid(config.empty_area_size.start, config.empty_area_size.end);
+    let band_size = range_mid(*config.band_size.start(), *config.band_size.end());
+    empty_area_size + config.band_count as f32 * (band_size + empty_area_size)
+}
+
+/// Star count of an average band layout, so every layout has a comparable amount of stars.
+fn star_count(config: &GalaxyConfig) -> u32 {
+    let stars_in_cluster =
+        (config.stars_in_cluster.start() + config.stars_in_cluster.end()) as f32 * 0.5;
+    (0..config.band_count)
+        .map(|index| (config.cluster_count(index) as f32 * stars_in_cluster) as u32)
+        .sum()
+}
+
+fn poisson_spacing(radius: f32, count: u32) -> f32 {
+    radius * (PI / count.max(1) as f32).sqrt() * POISSON_PACKING
+}
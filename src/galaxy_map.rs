@@ -0,0 +1,100 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::AppState,
+    galaxy_layout::Band,
+    launch_args::launch_arg,
+    star_generation::{GalaxySeed, NewStar, Star, StartSlot},
+};
+
+/// A complete galaxy, either generated by a layout or loaded from a `.ron`/`.json` map file.
+#[derive(Resource, Clone, Debug, Default, Serialize, Deserialize)]
+pub struct GalaxyMap {
+    #[serde(default)]
+    pub bands: Vec<Band>,
+    pub stars: Vec<NewStar>,
+}
+
+impl GalaxyMap {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        if is_json(path) {
+            serde_json::from_str(&contents).map_err(|err| err.to_string())
+        } else {
+            ron::from_str(&contents).map_err(|err| err.to_string())
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let path = path.as_ref();
+        let contents = if is_json(path) {
+            serde_json::to_string_pretty(self).map_err(|err| err.to_string())?
+        } else {
+            ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+                .map_err(|err| err.to_string())?
+        };
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    /// Loads the map given by the `map` launch argument, if any.
+    pub fn from_launch_args() -> Option<Self> {
+        let path = launch_arg("map")?;
+        match GalaxyMap::load(&path) {
+            Ok(galaxy_map) => Some(galaxy_map),
+            Err(err) => {
+                warn!("Failed to load map {path}: {err}");
+                None
+            }
+        }
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension()
+        .map_or(false, |extension| extension == "json")
+}
+
+pub struct GalaxyMapPlugin;
+
+impl Plugin for GalaxyMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, export_map.run_if(in_state(AppState::Playing)));
+    }
+}
+
+/// Writes the current galaxy to `galaxy_<seed>.ron` when F5 is pressed.
+/// The star each player started the match on is exported with their start slot, the index of
+/// the player in the match settings.
+fn export_map(
+    keyboard_input: Res<Input<KeyCode>>,
+    galaxy_seed: Res<GalaxySeed>,
+    q_band: Query<&Band>,
+    q_star: Query<(&Star, &Transform, Option<&StartSlot>)>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F5) {
+        return;
+    }
+
+    let galaxy_map = GalaxyMap {
+        bands: q_band.iter().cloned().collect(),
+        stars: q_star
+            .iter()
+            .map(|(star, transform, start_slot)| NewStar {
+                x: transform.translation.x,
+                y: transform.translation.y,
+                size: star.size,
+                start_slot: start_slot.map(|start_slot| start_slot.0),
+            })
+            .collect(),
+    };
+
+    let path = format!("galaxy_{}.ron", galaxy_seed.0);
+    match galaxy_map.save(&path) {
+        Ok(()) => info!("Exported map to {path}"),
+        Err(err) => warn!("Failed to export map to {path}: {err}"),
+    }
+}
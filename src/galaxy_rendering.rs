@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy_prototype_lyon::prelude::*;
+use ctrl_macros::ok_or_continue;
+
+use crate::{
+    app_state::MatchEntity,
+    galaxy_layout::Band,
+    players::Player,
+    ship::Fleet,
+    star_generation::{star_sprite_size, Star, BAND_Z_INDEX_START},
+};
+
+/// Adds sprites and shapes to the entities spawned by the simulation.
+pub struct GalaxyRenderingPlugin;
+
+/// Systems that add visuals to newly spawned simulation entities. UI that changes those visuals
+/// runs after it.
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct GalaxyRenderingSet;
+
+impl Plugin for GalaxyRenderingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                add_band_shapes,
+                add_star_sprites,
+                add_fleet_sprites,
+                change_fleet_ownership,
+                apply_deferred,
+            )
+                .chain()
+                .in_set(GalaxyRenderingSet),
+        );
+    }
+}
+
+fn add_band_shapes(query: Query<(Entity, &Band, &Transform), Added<Band>>, mut commands: Commands) {
+    for (entity, band, &transform) in query.iter() {
+        let shape = shapes::Circle {
+            radius: band.distance_from_center + band.size,
+            ..shapes::Circle::default()
+        };
+        commands.entity(entity).insert((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shape),
+                transform,
+                ..default()
+            },
+            Fill::color(Color::NONE),
+        ));
+    }
+
+    // Empty area before the first band
+    let first_band = query
+        .iter()
+        .map(|(_, band, _)| band)
+        .min_by(|a, b| a.distance_from_center.total_cmp(&b.distance_from_center));
+    if let Some(first_band) = first_band {
+        let shape = shapes::Circle {
+            radius: first_band.distance_from_center,
+            ..shapes::Circle::default()
+        };
+        commands.spawn((
+            ShapeBundle {
+                path: GeometryBuilder::build_as(&shape),
+                transform: Transform::from_xyz(0.0, 0.0, BAND_Z_INDEX_START + 1.0),
+                ..default()
+            },
+            Fill::color(Color::BLACK),
+            MatchEntity,
+        ));
+    }
+}
+
+fn add_star_sprites(
+    query: Query<(Entity, &Star), Added<Star>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, star) in query.iter() {
+        commands.entity(entity).insert((
+            Sprite {
+                custom_size: Some(Vec2::splat(star_sprite_size(star.size))),
+                ..default()
+            },
+            asset_server.load::<Image, _>("star_large.png"),
+        ));
+    }
+}
+
+fn add_fleet_sprites(
+    query: Query<(Entity, &Fleet), Added<Fleet>>,
+    q_player: Query<&Player>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    for (entity, fleet) in query.iter() {
+        let player = ok_or_continue!(q_player.get(fleet.player));
+
+        commands.entity(entity).insert((
+            Sprite {
+                color: player.color,
+                custom_size: Some(Vec2::new(10.0, 10.0)),
+                ..default()
+            },
+            asset_server.load::<Image, _>("enemy_E.png"),
+        ));
+    }
+}
+
+fn change_fleet_ownership(
+    mut query: Query<(&mut Sprite, &Fleet), Changed<Fleet>>,
+    q_player: Query<&Player>,
+) {
+    for (mut sprite, fleet) in query.iter_mut() {
+        let player = ok_or_continue!(q_player.get(fleet.player));
+
+        sprite.color = player.color;
+    }
+}
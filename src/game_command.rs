@@ -0,0 +1,210 @@
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::{AppState, SimulationSet},
+    players::{OwnedBy, PlayerId},
+    ship::{launch_fleet, AttachedFleet, Battle, Fleet, FleetId, FlyTo, NextFleetId, RallyPoint},
+    star_generation::StarId,
+};
+
+/// An order of a player, issued by the human through input or by the AI.
+///
+/// Players change the match only through these, so a match is reproduced from its seed and
+/// the commands applied in each tick, and a command is what gets sent over the network. They
+/// refer to stars, players and fleets by the ids that are the same in every run of a match.
+#[derive(Event, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum GameCommand {
+    /// Sends `amount` ships, or all there are, from one of the player's stars to another star.
+    SendFleet {
+        player: PlayerId,
+        from: StarId,
+        to: StarId,
+        amount: f32,
+    },
+    /// Sends one of the player's fleets in flight to another star.
+    Redirect {
+        player: PlayerId,
+        fleet: FleetId,
+        to: StarId,
+    },
+    /// Sends one of the player's fleets in flight back to the star it was launched from.
+    Recall { player: PlayerId, fleet: FleetId },
+    /// Keeps sending the ships produced at one of the player's stars on to another of their
+    /// stars, or stops it with `None`.
+    SetRally {
+        player: PlayerId,
+        star: StarId,
+        rally: Option<StarId>,
+    },
+}
+
+impl GameCommand {
+    pub fn player(&self) -> PlayerId {
+        match *self {
+            GameCommand::SendFleet { player, .. }
+            | GameCommand::Redirect { player, .. }
+            | GameCommand::Recall { player, .. }
+            | GameCommand::SetRally { player, .. } => player,
+        }
+    }
+}
+
+/// A [`GameCommand`] that passed validation, sent in the tick it was applied in.
+#[derive(Event, Clone, Debug)]
+pub struct GameCommandApplied(pub GameCommand);
+
+/// Applies the [`GameCommand`]s sent since the previous tick at the start of the next one.
+///
+/// The events aren't cleared every frame like other events, so commands sent while the
+/// simulation is paused or between ticks aren't lost.
+pub struct GameCommandPlugin;
+
+impl Plugin for GameCommandPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Events<GameCommand>>()
+            .add_event::<GameCommandApplied>()
+            .add_systems(
+                FixedUpdate,
+                apply_game_commands.in_set(SimulationSet::Commands),
+            )
+            .add_systems(OnEnter(AppState::MainMenu), clear_game_commands)
+            .add_systems(OnExit(AppState::GameOver), clear_game_commands);
+    }
+}
+
+/// Commands left over from a match don't carry over to the next one.
+fn clear_game_commands(mut game_commands: ResMut<Events<GameCommand>>) {
+    game_commands.clear();
+}
+
+/// Validates each command against the current state of the match and applies it. Commands a
+/// player isn't allowed to give, or that refer to something that's gone, are dropped.
+pub fn apply_game_commands(
+    mut game_commands: ResMut<Events<GameCommand>>,
+    mut ev_applied: EventWriter<GameCommandApplied>,
+    q_player: Query<(Entity, &PlayerId)>,
+    q_star: Query<(Entity, &StarId, Option<&OwnedBy>, Option<&AttachedFleet>)>,
+    mut q_fleet: Query<(Entity, &mut Fleet, Option<&FleetId>, Option<&mut FlyTo>)>,
+    mut next_fleet_id: ResMut<NextFleetId>,
+    mut commands: Commands,
+) {
+    if game_commands.is_empty() {
+        return;
+    }
+
+    let players: HashMap<_, _> = q_player
+        .iter()
+        .map(|(entity, &player_id)| (player_id, entity))
+        .collect();
+    let stars: HashMap<_, _> = q_star
+        .iter()
+        .map(|(entity, &star_id, _, _)| (star_id, entity))
+        .collect();
+    let fleets: HashMap<_, _> = q_fleet
+        .iter()
+        .filter_map(|(entity, _, fleet_id, _)| Some((*fleet_id?, entity)))
+        .collect();
+
+    for game_command in game_commands.drain() {
+        let Some(&player) = players.get(&game_command.player()) else {
+            continue;
+        };
+
+        match game_command {
+            GameCommand::SendFleet {
+                from, to, amount, ..
+            } => {
+                let (Some(&from), Some(&to)) = (stars.get(&from), stars.get(&to)) else {
+                    continue;
+                };
+                let Ok((_, _, Some(owned_by), Some(attached_fleet))) = q_star.get(from) else {
+                    continue;
+                };
+                if owned_by.player != player || from == to {
+                    continue;
+                }
+                let Ok((_, mut fleet, _, _)) = q_fleet.get_mut(attached_fleet.fleet_id) else {
+                    continue;
+                };
+                if !amount.is_finite() {
+                    continue;
+                }
+                let size = amount.min(fleet.size);
+                if size <= 0.0 {
+                    continue;
+                }
+
+                fleet.size -= size;
+                launch_fleet(&mut commands, &mut next_fleet_id, player, from, to, size);
+            }
+            GameCommand::Redirect { fleet, to, .. } => {
+                let (Some(&fleet), Some(&to)) = (fleets.get(&fleet), stars.get(&to)) else {
+                    continue;
+                };
+                let Ok((_, fleet_state, _, Some(mut fly_to))) = q_fleet.get_mut(fleet) else {
+                    continue;
+                };
+                if fleet_state.player != player || fly_to.destination_star == to {
+                    continue;
+                }
+
+                fly_to.destination_star = to;
+                // Leaving a battle, it starts over at the new destination
+                commands.entity(fleet).remove::<Battle>();
+            }
+            GameCommand::Recall { fleet, .. } => {
+                let Some(&fleet) = fleets.get(&fleet) else {
+                    continue;
+                };
+                let Ok((_, fleet_state, _, Some(mut fly_to))) = q_fleet.get_mut(fleet) else {
+                    continue;
+                };
+                if fleet_state.player != player || fly_to.destination_star == fly_to.origin_star {
+                    continue;
+                }
+
+                fly_to.destination_star = fly_to.origin_star;
+                commands.entity(fleet).remove::<Battle>();
+            }
+            GameCommand::SetRally { star, rally, .. } => {
+                let Some(&star) = stars.get(&star) else {
+                    continue;
+                };
+                let Ok((_, _, Some(owned_by), _)) = q_star.get(star) else {
+                    continue;
+                };
+                if owned_by.player != player {
+                    continue;
+                }
+
+                match rally {
+                    None => {
+                        commands.entity(star).remove::<RallyPoint>();
+                    }
+                    Some(rally) => {
+                        let Some(&rally) = stars.get(&rally) else {
+                            continue;
+                        };
+                        // Ships are only rallied to the player's own stars
+                        let Ok((_, _, Some(rally_owner), _)) = q_star.get(rally) else {
+                            continue;
+                        };
+                        if rally_owner.player != player {
+                            continue;
+                        }
+
+                        commands.entity(star).insert(RallyPoint {
+                            player,
+                            star: rally,
+                        });
+                    }
+                }
+            }
+        }
+
+        ev_applied.send(GameCommandApplied(game_command));
+    }
+}
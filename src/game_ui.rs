@@ -2,12 +2,18 @@ use std::collections::HashMap;
 
 use bevy::prelude::*;
 use bevy_prototype_lyon::{prelude::*, shapes};
-use ctrl_macros::{ok_or_continue, ok_or_return, some_or_return};
+use ctrl_macros::{ok_or_continue, ok_or_return};
 
 use crate::{
+    app_state::{AppState, MatchEntity},
+    control::{SendAmount, TypingSendAmount},
+    galaxy_rendering::GalaxyRenderingSet,
+    palette::MarkerShape,
     players::{OwnedBy, Player},
     ship::{AttachedFleet, Fleet},
-    star_generation::Star,
+    simulation_clock::SimulationClock,
+    star_generation::{GalaxySeed, Star},
+    target_assignment::TargetingMode,
 };
 
 pub struct GameUiPlugin;
@@ -21,6 +27,15 @@ pub struct OwnershipCircle;
 #[derive(Component)]
 pub struct PlayerStarText;
 
+#[derive(Component)]
+pub struct PlayerMarker;
+
+#[derive(Component)]
+pub struct SimulationSpeedText;
+
+#[derive(Component)]
+pub struct SendAmountText;
+
 #[derive(Component)]
 pub struct PlayerScoreHolder;
 
@@ -29,16 +44,28 @@ pub struct PlayerScore {
     player: Entity,
 }
 
-#[derive(Component)]
-pub struct ResultText;
-
 impl Plugin for GameUiPlugin {
     fn build(&self, app: &mut App) {
         app.add_systems(Startup, setup_player_score_ui)
+            .add_systems(
+                Update,
+                (
+                    setup_seed_ui,
+                    setup_simulation_speed_ui,
+                    setup_send_amount_ui,
+                )
+                    .run_if(resource_exists_and_changed::<GalaxySeed>()),
+            )
+            .add_systems(Update, update_simulation_speed_text)
+            .add_systems(Update, update_send_amount_text)
+            .add_systems(
+                Update,
+                simulation_clock_hotkeys.run_if(in_state(AppState::Playing)),
+            )
             .add_systems(Update, add_player_score)
             .add_systems(Update, update_player_score)
-            .add_systems(Update, player_assigned_star)
-            .add_systems(Update, star_assignment_changed)
+            .add_systems(Update, player_assigned_star.after(GalaxyRenderingSet))
+            .add_systems(Update, star_assignment_changed.after(GalaxyRenderingSet))
             .add_systems(Update, star_resource_label)
             .add_systems(Update, update_star_text);
     }
@@ -46,7 +73,7 @@ impl Plugin for GameUiPlugin {
 
 fn player_assigned_star(
     mut query: Query<(Entity, &mut Sprite, &OwnedBy), (With<Star>, Added<OwnedBy>)>,
-    player_query: Query<&Player>,
+    player_query: Query<(&Player, Option<&MarkerShape>)>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
 ) {
@@ -64,10 +91,25 @@ fn player_assigned_star(
     };
 
     for (entity, mut sprite, owned_by) in query.iter_mut() {
-        let player = ok_or_continue!(player_query.get(owned_by.player));
+        let (player, marker_shape) = ok_or_continue!(player_query.get(owned_by.player));
 
         sprite.color = player.color;
 
+        if let Some(&marker_shape) = marker_shape {
+            let marker = commands
+                .spawn((
+                    ShapeBundle {
+                        path: marker_path(marker_shape),
+                        transform: Transform::from_xyz(-22.0, -15.0, 1.0),
+                        ..default()
+                    },
+                    Fill::color(player.color),
+                ))
+                .insert(PlayerMarker)
+                .id();
+            commands.entity(entity).add_child(marker);
+        }
+
         let label = commands
             .spawn(Text2dBundle {
                 text: Text::from_section(player.name.to_string(), text_style.clone())
@@ -100,11 +142,12 @@ fn player_assigned_star(
 fn star_assignment_changed(
     mut query_star: Query<(&mut Sprite, &OwnedBy, &Children), (With<Star>, Changed<OwnedBy>)>,
     mut q_player_star_text: Query<&mut Text, With<PlayerStarText>>,
-    mut q_ownership_circle: Query<&mut Fill, With<OwnershipCircle>>,
-    player_query: Query<&Player>,
+    mut q_ownership_circle: Query<&mut Fill, (With<OwnershipCircle>, Without<PlayerMarker>)>,
+    mut q_player_marker: Query<(&mut Path, &mut Fill), With<PlayerMarker>>,
+    player_query: Query<(&Player, Option<&MarkerShape>)>,
 ) {
     for (mut sprite, owned_by, children) in query_star.iter_mut() {
-        let player = ok_or_continue!(player_query.get(owned_by.player));
+        let (player, marker_shape) = ok_or_continue!(player_query.get(owned_by.player));
 
         sprite.color = player.color;
 
@@ -121,10 +164,44 @@ fn star_assignment_changed(
             if let Ok(mut fill) = fill {
                 fill.color = *player.color.clone().set_a(0.2);
             }
+
+            let marker = q_player_marker.get_mut(child);
+            if let (Ok((mut path, mut fill)), Some(&marker_shape)) = (marker, marker_shape) {
+                *path = marker_path(marker_shape);
+                fill.color = player.color;
+            }
         }
     }
 }
 
+fn marker_path(marker_shape: MarkerShape) -> Path {
+    match marker_shape {
+        MarkerShape::Circle => GeometryBuilder::build_as(&shapes::Circle {
+            radius: 4.0,
+            ..shapes::Circle::default()
+        }),
+        MarkerShape::Square => GeometryBuilder::build_as(&shapes::Rectangle {
+            extents: Vec2::new(8.0, 8.0),
+            origin: shapes::RectangleOrigin::Center,
+        }),
+        MarkerShape::Triangle => GeometryBuilder::build_as(&shapes::RegularPolygon {
+            sides: 3,
+            feature: shapes::RegularPolygonFeature::Radius(5.0),
+            ..shapes::RegularPolygon::default()
+        }),
+        MarkerShape::Diamond => GeometryBuilder::build_as(&shapes::RegularPolygon {
+            sides: 4,
+            feature: shapes::RegularPolygonFeature::Radius(5.0),
+            ..shapes::RegularPolygon::default()
+        }),
+        MarkerShape::Hexagon => GeometryBuilder::build_as(&shapes::RegularPolygon {
+            sides: 6,
+            feature: shapes::RegularPolygonFeature::Radius(5.0),
+            ..shapes::RegularPolygon::default()
+        }),
+    }
+}
+
 fn star_resource_label(
     query: Query<(Entity, &Star), Added<Star>>,
     mut commands: Commands,
@@ -171,7 +248,7 @@ fn update_star_text(
     q_fleet: Query<&Fleet>,
 ) {
     for (attached_fleet, children) in q_attached_fleet.iter() {
-        let fleet = q_fleet.get(attached_fleet.fleet_id).unwrap();
+        let fleet = ok_or_continue!(q_fleet.get(attached_fleet.fleet_id));
 
         for &child in children.iter() {
             let text = q_star_text.get_mut(child);
@@ -182,7 +259,7 @@ fn update_star_text(
     }
 }
 
-fn setup_player_score_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+fn setup_player_score_ui(mut commands: Commands) {
     // PlayerScoreHolder
     commands
         .spawn(NodeBundle {
@@ -195,30 +272,132 @@ fn setup_player_score_ui(mut commands: Commands, asset_server: Res<AssetServer>)
             ..default()
         })
         .insert(PlayerScoreHolder);
+}
 
+fn setup_seed_ui(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    galaxy_seed: Res<GalaxySeed>,
+) {
     commands
         .spawn(TextBundle {
             style: Style {
                 position_type: PositionType::Absolute,
-                left: Val::Percent(45.0),
-                right: Val::Percent(50.0),
-                bottom: Val::Percent(0.0),
+                left: Val::Px(15.0),
+                bottom: Val::Px(10.0),
+                ..default()
+            },
+            text: Text::from_section(
+                format!("Seed: {}", galaxy_seed.0),
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            ),
+            ..default()
+        })
+        .insert(MatchEntity);
+}
 
+fn setup_simulation_speed_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(15.0),
+                bottom: Val::Px(34.0),
                 ..default()
             },
             text: Text::from_section(
-                "".to_string(),
+                "",
                 TextStyle {
                     font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                    font_size: 40.0,
+                    font_size: 20.0,
                     color: Color::WHITE,
                 },
-            )
-            .with_alignment(TextAlignment::Center),
+            ),
+            ..default()
+        })
+        .insert(SimulationSpeedText)
+        .insert(MatchEntity);
+}
 
+fn setup_send_amount_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(15.0),
+                bottom: Val::Px(58.0),
+                ..default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            ),
             ..default()
         })
-        .insert(ResultText);
+        .insert(SendAmountText)
+        .insert(MatchEntity);
+}
+
+fn update_send_amount_text(
+    send_amount: Res<SendAmount>,
+    typing: Res<TypingSendAmount>,
+    targeting_mode: Res<TargetingMode>,
+    mut q_text: Query<&mut Text, With<SendAmountText>>,
+) {
+    for mut text in q_text.iter_mut() {
+        let amount = match *send_amount {
+            SendAmount::Exact(ships) => format!("{ships} ships"),
+            SendAmount::Fraction(fraction) => format!("{:.0}%", fraction * 100.0),
+        };
+        let hint = if typing.0 {
+            "type the number, X: done"
+        } else {
+            "1-4: quarters, X: exact, T: targets"
+        };
+        text.sections[0].value = format!(
+            "Send: {amount}, targets: {} ({hint})",
+            targeting_mode.name()
+        );
+    }
+}
+
+fn update_simulation_speed_text(
+    clock: Res<SimulationClock>,
+    mut q_text: Query<&mut Text, With<SimulationSpeedText>>,
+) {
+    for mut text in q_text.iter_mut() {
+        text.sections[0].value = if clock.paused() {
+            "Paused (Space: resume, .: step)".to_string()
+        } else {
+            format!("Speed: {}x", clock.speed())
+        };
+    }
+}
+
+fn simulation_clock_hotkeys(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut clock: ResMut<SimulationClock>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        clock.toggle_pause();
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Equals, KeyCode::NumpadAdd]) {
+        clock.faster();
+    }
+    if keyboard_input.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        clock.slower();
+    }
+    if keyboard_input.just_pressed(KeyCode::Period) {
+        clock.step();
+    }
 }
 
 fn add_player_score(
@@ -252,6 +431,7 @@ fn add_player_score(
             .insert(PlayerScore {
                 player: player_entity,
             })
+            .insert(MatchEntity)
             .id();
 
         commands.entity(holder).add_child(player_score);
@@ -261,14 +441,11 @@ fn add_player_score(
 fn update_player_score(
     q_owned_star: Query<&OwnedBy, With<Star>>,
     q_player: Query<&Player>,
-    mut q_player_score: Query<(&mut Text, &PlayerScore), Without<ResultText>>,
-    mut q_result_text: Query<&mut Text, With<ResultText>>,
+    mut q_player_score: Query<(&mut Text, &PlayerScore)>,
 ) {
     let mut score_map = HashMap::new();
-    let mut total_stars = 0;
     for owned_by in q_owned_star.iter() {
         *score_map.entry(owned_by.player).or_insert(0) += 1;
-        total_stars += 1;
     }
 
     for (mut text, player_score) in q_player_score.iter_mut() {
@@ -279,23 +456,4 @@ fn update_player_score(
             score_map.get(&player_score.player).unwrap_or(&0)
         );
     }
-
-    let mut human = None;
-    for (_, player_score) in q_player_score.iter() {
-        let player = ok_or_continue!(q_player.get(player_score.player));
-        if player.is_human {
-            human = Some(player_score.player);
-            break;
-        }
-    }
-
-    let &our_score = score_map.get(&some_or_return!(human)).unwrap_or(&0);
-    let mut result_text = ok_or_return!(q_result_text.get_single_mut());
-    result_text.sections[0].value = if our_score == 0 {
-        "Defeat!\nRefresh to play again".to_string()
-    } else if our_score as f32 > total_stars as f32 * 0.8 {
-        "Victory!\nRefresh to play again".to_string()
-    } else {
-        "TEST".to_string()
-    };
 }
@@ -0,0 +1,206 @@
+use bevy::{prelude::*, sprite::Anchor};
+use ctrl_macros::ok_or_continue;
+
+use crate::{
+    app_state::{AppState, MatchEntity},
+    players::{OwnedBy, Player},
+    ship::{AttachedFleet, Fleet, FleetArrival, FlyTo},
+    simulation_clock::SimulationClock,
+    star_generation::{GalaxySeed, Star},
+};
+
+/// Real seconds a launch warning stays on screen.
+const WARNING_SECONDS: f32 = 5.0;
+
+/// Warns the human player of hostile fleets flying to their stars.
+pub struct IncomingUiPlugin;
+
+/// Lists the hostile fleets flying to a star owned by the human player, above its resource label.
+#[derive(Component)]
+pub struct IncomingText;
+
+/// Warning about the latest large hostile fleet launched at the human player.
+#[derive(Component)]
+pub struct LaunchWarning {
+    expires_at: f32,
+}
+
+impl Plugin for IncomingUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            setup_launch_warning.run_if(resource_exists_and_changed::<GalaxySeed>()),
+        )
+        .add_systems(Update, add_incoming_text)
+        .add_systems(
+            Update,
+            (update_incoming_text, warn_of_large_launches).run_if(in_state(AppState::Playing)),
+        )
+        .add_systems(Update, expire_launch_warnings);
+    }
+}
+
+fn setup_launch_warning(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(TextBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Px(15.0),
+                top: Val::Px(10.0),
+                ..default()
+            },
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 20.0,
+                    color: Color::WHITE,
+                },
+            ),
+            ..default()
+        })
+        .insert(LaunchWarning { expires_at: 0.0 })
+        .insert(MatchEntity);
+}
+
+fn add_incoming_text(
+    query: Query<Entity, Added<Star>>,
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 40.0,
+        color: Color::WHITE,
+    };
+
+    for entity in query.iter() {
+        let label = commands
+            .spawn(Text2dBundle {
+                text: Text::from_section("", text_style.clone())
+                    .with_alignment(TextAlignment::Center),
+                text_anchor: Anchor::BottomCenter,
+                transform: Transform::from_xyz(0.0, 20.0, 0.0).with_scale(Vec3::new(0.2, 0.2, 0.2)),
+                ..default()
+            })
+            .insert(IncomingText)
+            .id();
+
+        commands.entity(entity).add_child(label);
+    }
+}
+
+/// Shows the size and arrival countdown of each hostile fleet, soonest first, in the colour of
+/// the attacking player.
+fn update_incoming_text(
+    q_fly_to: Query<(Entity, &FlyTo, &Fleet)>,
+    q_star: Query<(&OwnedBy, &Children), With<Star>>,
+    q_player: Query<&Player>,
+    mut q_incoming_text: Query<&mut Text, With<IncomingText>>,
+    fleet_arrival: FleetArrival,
+    clock: Res<SimulationClock>,
+) {
+    let mut incoming: Vec<_> = q_fly_to
+        .iter()
+        .filter_map(|(entity, fly_to, fleet)| {
+            let (owned_by, _) = q_star.get(fly_to.destination_star).ok()?;
+            if owned_by.player == fleet.player || !q_player.get(owned_by.player).ok()?.is_human {
+                return None;
+            }
+            let seconds = real_seconds(fleet_arrival.seconds(entity)?, &clock);
+            Some((fly_to.destination_star, fleet, seconds))
+        })
+        .collect();
+    incoming.sort_by(|a, b| a.2.total_cmp(&b.2));
+
+    for mut text in q_incoming_text.iter_mut() {
+        text.sections.truncate(1);
+        text.sections[0].value.clear();
+    }
+
+    for (star, fleet, seconds) in incoming {
+        let (_, children) = ok_or_continue!(q_star.get(star));
+        let color = q_player
+            .get(fleet.player)
+            .map_or(Color::WHITE, |player| player.color);
+        for &child in children.iter() {
+            let mut text = ok_or_continue!(q_incoming_text.get_mut(child));
+            let mut style = text.sections[0].style.clone();
+            style.color = color;
+            // No trailing line break, the label grows upwards from above the resource label
+            let separator = if text.sections.len() > 1 { "\n" } else { "" };
+            text.sections.push(TextSection {
+                value: format!("{separator}{:.1} in {:.0}s", fleet.size, seconds.ceil()),
+                style,
+            });
+        }
+    }
+}
+
+/// Fleets launched at one of the human player's stars that would capture it on arrival raise a
+/// warning.
+fn warn_of_large_launches(
+    q_launched: Query<(Entity, &FlyTo, &Fleet), Added<FlyTo>>,
+    q_star: Query<(&OwnedBy, Option<&AttachedFleet>), With<Star>>,
+    q_fleet: Query<&Fleet, Without<FlyTo>>,
+    q_player: Query<&Player>,
+    mut q_warning: Query<(&mut Text, &mut LaunchWarning)>,
+    fleet_arrival: FleetArrival,
+    clock: Res<SimulationClock>,
+    time: Res<Time>,
+) {
+    let mut latest_warning = None;
+    for (entity, fly_to, fleet) in q_launched.iter() {
+        let (owned_by, attached_fleet) = ok_or_continue!(q_star.get(fly_to.destination_star));
+        if owned_by.player == fleet.player {
+            continue;
+        }
+        let defender = ok_or_continue!(q_player.get(owned_by.player));
+        if !defender.is_human {
+            continue;
+        }
+        let defenders = attached_fleet
+            .and_then(|attached_fleet| q_fleet.get(attached_fleet.fleet_id).ok())
+            .map_or(0.0, |defending_fleet| defending_fleet.size);
+        if fleet.size <= defenders {
+            continue;
+        }
+        let attacker = ok_or_continue!(q_player.get(fleet.player));
+        let Some(seconds) = fleet_arrival.seconds(entity) else {
+            continue;
+        };
+        let seconds = real_seconds(seconds, &clock);
+
+        let message = format!(
+            "{} launched {:.1} ships at your star, arriving in {:.0}s",
+            attacker.name,
+            fleet.size,
+            seconds.ceil()
+        );
+        latest_warning = Some((message, attacker.color));
+    }
+
+    let Some((message, color)) = latest_warning else {
+        return;
+    };
+    // A newer warning replaces the one on screen
+    for (mut text, mut warning) in q_warning.iter_mut() {
+        text.sections[0].value = message.clone();
+        text.sections[0].style.color = color;
+        warning.expires_at = time.raw_elapsed_seconds() + WARNING_SECONDS;
+    }
+}
+
+fn expire_launch_warnings(mut query: Query<(&mut Text, &LaunchWarning)>, time: Res<Time>) {
+    for (mut text, warning) in query.iter_mut() {
+        if time.raw_elapsed_seconds() >= warning.expires_at && !text.sections[0].value.is_empty() {
+            text.sections[0].value.clear();
+        }
+    }
+}
+
+/// Arrival times are shown in real seconds at the current simulation speed, like the time a
+/// launch warning stays on screen.
+fn real_seconds(game_seconds: f32, clock: &SimulationClock) -> f32 {
+    game_seconds / clock.speed()
+}
@@ -0,0 +1,26 @@
+//! Launch options come from the command line natively (`--seed 42` or `--seed=42`)
+//! and from the page URL on the web (`?seed=42`).
+
+#[cfg(not(target_arch = "wasm32"))]
+pub fn launch_arg(name: &str) -> Option<String> {
+    let flag = format!("--{name}");
+    let flag_with_value = format!("--{name}=");
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            return args.next();
+        }
+        if let Some(value) = arg.strip_prefix(&flag_with_value) {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn launch_arg(name: &str) -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    let params = web_sys::UrlSearchParams::new_with_str(&search).ok()?;
+    params.get(name)
+}
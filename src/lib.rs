@@ -0,0 +1,96 @@
+#![allow(clippy::type_complexity)]
+
+use bevy::{app::PluginGroupBuilder, prelude::*};
+use bevy_prototype_lyon::plugin::ShapePlugin;
+
+use ai::AiPlugin;
+use app_state::AppStatePlugin;
+use battle_ui::BattleUiPlugin;
+use camera::CameraPlugin;
+use control::ControlPlugin;
+use debug::DebugPlugin;
+use galaxy_map::GalaxyMapPlugin;
+use galaxy_rendering::GalaxyRenderingPlugin;
+use game_command::GameCommandPlugin;
+use game_ui::GameUiPlugin;
+use incoming_ui::IncomingUiPlugin;
+use match_setup_ui::MatchSetupUiPlugin;
+use menu_ui::MenuUiPlugin;
+use players::PlayerPlugin;
+use replay::ReplayPlugin;
+use replay_ui::ReplayUiPlugin;
+use selection::SelectionPlugin;
+use selection_ui::SelectionUIPlugin;
+use ship::ShipPlugin;
+use simulation_clock::SimulationClockPlugin;
+use star_generation::StarGenerationPlugin;
+
+pub mod ai;
+pub mod app_state;
+pub mod battle_ui;
+pub mod camera;
+pub mod control;
+pub mod debug;
+pub mod galaxy_config;
+pub mod galaxy_layout;
+pub mod galaxy_map;
+pub mod galaxy_rendering;
+pub mod game_command;
+pub mod game_ui;
+pub mod incoming_ui;
+pub mod launch_args;
+pub mod match_settings;
+pub mod match_setup_ui;
+pub mod menu_ui;
+pub mod palette;
+pub mod players;
+pub mod replay;
+pub mod replay_ui;
+pub mod selection;
+pub mod selection_ui;
+pub mod ship;
+pub mod simulation_clock;
+pub mod star_generation;
+pub mod start_placement;
+pub mod target_assignment;
+pub mod top_down_camera;
+
+/// Gameplay without any rendering, input or UI. Runs headless next to `MinimalPlugins`.
+pub struct StarsIoSimulationPlugins;
+
+impl PluginGroup for StarsIoSimulationPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(AppStatePlugin)
+            .add(SimulationClockPlugin)
+            .add(StarGenerationPlugin)
+            .add(PlayerPlugin)
+            .add(GameCommandPlugin)
+            .add(ShipPlugin)
+            .add(AiPlugin)
+            .add(ControlPlugin)
+            .add(ReplayPlugin)
+    }
+}
+
+/// Rendering, camera, input and UI on top of [`StarsIoSimulationPlugins`]. Needs `DefaultPlugins`.
+pub struct StarsIoUiPlugins;
+
+impl PluginGroup for StarsIoUiPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(ShapePlugin)
+            .add(BattleUiPlugin)
+            .add(CameraPlugin)
+            .add(DebugPlugin)
+            .add(GalaxyMapPlugin)
+            .add(GalaxyRenderingPlugin)
+            .add(GameUiPlugin)
+            .add(IncomingUiPlugin)
+            .add(MatchSetupUiPlugin)
+            .add(MenuUiPlugin)
+            .add(ReplayUiPlugin)
+            .add(SelectionPlugin)
+            .add(SelectionUIPlugin)
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    galaxy_config::{GalaxyConfig, GalaxyPreset},
+    palette::PaletteKind,
+    star_generation::GalaxySeed,
+};
+
+pub const MAX_AI_PLAYERS: usize = 15;
+const DEFAULT_AI_PLAYERS: usize = 10;
+
+/// How an AI player sizes its attacks, see `ai::send_ratio`. Added to AI player entities.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn next(self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PlayerSettings {
+    pub name: String,
+    pub is_human: bool,
+    pub color: Color,
+    /// Only used by AI players.
+    pub difficulty: Difficulty,
+}
+
+impl PlayerSettings {
+    pub fn ai(index: usize, color: Color) -> Self {
+        PlayerSettings {
+            name: format!("AI: {}", index + 1),
+            is_human: false,
+            color,
+            difficulty: Difficulty::default(),
+        }
+    }
+}
+
+/// Everything chosen on the match setup screen, read by `PlayerPlugin` and
+/// `StarGenerationPlugin` when the match starts.
+#[derive(Resource, Clone, Debug)]
+pub struct MatchSettings {
+    /// The human player comes first, followed by the AI opponents.
+    pub players: Vec<PlayerSettings>,
+    pub preset: GalaxyPreset,
+    pub galaxy: GalaxyConfig,
+    pub seed: u64,
+    pub palette: PaletteKind,
+    /// Draw each player's `MarkerShape` next to their stars, see `markers_shown`.
+    pub show_markers: bool,
+}
+
+impl MatchSettings {
+    /// The default settings with the preset, layout and seed given as launch arguments. Without
+    /// a seed a random one is picked.
+    pub fn from_launch_args() -> Self {
+        MatchSettings {
+            preset: GalaxyPreset::from_launch_args(),
+            galaxy: GalaxyConfig::from_launch_args(),
+            seed: GalaxySeed::from_launch_args().0,
+            ..default()
+        }
+    }
+
+    pub fn ai_count(&self) -> usize {
+        self.players
+            .iter()
+            .filter(|player| !player.is_human)
+            .count()
+    }
+
+    pub fn add_ai(&mut self) {
+        let index = self.ai_count();
+        let color = self
+            .palette_colors()
+            .into_iter()
+            .find(|&color| self.players.iter().all(|player| player.color != color))
+            .unwrap_or(Color::WHITE);
+        self.players.push(PlayerSettings::ai(index, color));
+    }
+
+    pub fn set_palette(&mut self, palette: PaletteKind) {
+        self.palette = palette;
+        let colors = palette.colors(self.players.len());
+        for (player, color) in self.players.iter_mut().zip(colors) {
+            player.color = color;
+        }
+    }
+
+    /// Gives the player the next palette colour that no other player is using.
+    pub fn cycle_color(&mut self, index: usize) {
+        let colors = self.palette_colors();
+        let current = colors
+            .iter()
+            .position(|&color| color == self.players[index].color)
+            .unwrap_or_default();
+        let next = (1..colors.len())
+            .map(|offset| colors[(current + offset) % colors.len()])
+            .find(|&color| self.players.iter().all(|player| player.color != color))
+            .unwrap_or(colors[(current + 1) % colors.len()]);
+        self.players[index].color = next;
+    }
+
+    fn palette_colors(&self) -> Vec<Color> {
+        self.palette.colors(MAX_AI_PLAYERS + 1)
+    }
+
+    /// Markers are drawn when turned on, and whenever players share a colour, as the
+    /// colour-blind safe palette runs out after 8 players.
+    pub fn markers_shown(&self) -> bool {
+        self.show_markers || self.colors_repeat()
+    }
+
+    pub fn colors_repeat(&self) -> bool {
+        self.players.iter().enumerate().any(|(index, player)| {
+            self.players[..index]
+                .iter()
+                .any(|other| other.color == player.color)
+        })
+    }
+
+    pub fn set_preset(&mut self, preset: GalaxyPreset) {
+        self.preset = preset;
+        self.galaxy = GalaxyConfig {
+            layout: self.galaxy.layout,
+            ..GalaxyConfig::from_preset(preset)
+        };
+    }
+}
+
+/// A human against the default number of AI players on the standard preset with seed 0,
+/// without reading the launch arguments.
+impl Default for MatchSettings {
+    fn default() -> Self {
+        let palette = PaletteKind::default();
+        let mut colors = palette.colors(DEFAULT_AI_PLAYERS + 1).into_iter();
+        let human = PlayerSettings {
+            name: "You".to_string(),
+            is_human: true,
+            color: colors.next().unwrap(),
+            difficulty: Difficulty::default(),
+        };
+
+        MatchSettings {
+            players: std::iter::once(human)
+                .chain(
+                    colors
+                        .enumerate()
+                        .map(|(index, color)| PlayerSettings::ai(index, color)),
+                )
+                .collect(),
+            preset: GalaxyPreset::Standard,
+            galaxy: GalaxyConfig::default(),
+            seed: 0,
+            palette,
+            show_markers: false,
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use bevy::prelude::*;
+
+use crate::{
+    app_state::AppState,
+    match_settings::{MatchSettings, MAX_AI_PLAYERS},
+};
+
+const MAX_NAME_LENGTH: usize = 16;
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.25);
+
+pub struct MatchSetupUiPlugin;
+
+#[derive(Component)]
+struct MatchSetupRoot;
+
+#[derive(Component, Clone, Copy)]
+enum SetupButton {
+    Preset,
+    Seed,
+    RandomSeed,
+    AddAi,
+    RemoveAi,
+    Palette,
+    Markers,
+    Name(usize),
+    Color(usize),
+    Difficulty(usize),
+    Start,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TextField {
+    Seed,
+    Name(usize),
+}
+
+/// Text field currently receiving keyboard input.
+#[derive(Resource, Default)]
+struct EditedField(Option<TextField>);
+
+impl Plugin for MatchSetupUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditedField>()
+            .add_systems(
+                Update,
+                (setup_button_pressed, edit_text_field, rebuild_setup_ui)
+                    .chain()
+                    .run_if(in_state(AppState::MatchSetup)),
+            )
+            .add_systems(OnExit(AppState::MatchSetup), despawn_setup_ui);
+    }
+}
+
+fn setup_button_pressed(
+    q_button: Query<(&Interaction, &SetupButton), Changed<Interaction>>,
+    mut settings: ResMut<MatchSettings>,
+    mut edited_field: ResMut<EditedField>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    for (interaction, &button) in q_button.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        edited_field.0 = None;
+        match button {
+            SetupButton::Preset => {
+                let preset = settings.preset.next();
+                settings.set_preset(preset);
+            }
+            SetupButton::Seed => edited_field.0 = Some(TextField::Seed),
+            SetupButton::RandomSeed => settings.seed = rand::random(),
+            SetupButton::AddAi => {
+                if settings.ai_count() < MAX_AI_PLAYERS {
+                    settings.add_ai();
+                }
+            }
+            SetupButton::RemoveAi => {
+                if settings.ai_count() > 1 {
+                    if let Some(index) =
+                        settings.players.iter().rposition(|player| !player.is_human)
+                    {
+                        settings.players.remove(index);
+                    }
+                }
+            }
+            SetupButton::Palette => {
+                let palette = settings.palette.next();
+                settings.set_palette(palette);
+            }
+            SetupButton::Markers => settings.show_markers = !settings.show_markers,
+            SetupButton::Name(index) => edited_field.0 = Some(TextField::Name(index)),
+            SetupButton::Color(index) => settings.cycle_color(index),
+            SetupButton::Difficulty(index) => {
+                let difficulty = settings.players[index].difficulty.next();
+                settings.players[index].difficulty = difficulty;
+            }
+            SetupButton::Start => next_state.set(AppState::Playing),
+        }
+    }
+}
+
+fn edit_text_field(
+    mut ev_character: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut edited_field: ResMut<EditedField>,
+    mut settings: ResMut<MatchSettings>,
+) {
+    let Some(field) = edited_field.0 else {
+        ev_character.clear();
+        return;
+    };
+
+    for event in ev_character.iter() {
+        if event.char.is_control() {
+            continue;
+        }
+
+        match field {
+            TextField::Seed => {
+                if let Some(digit) = event.char.to_digit(10) {
+                    settings.seed = settings
+                        .seed
+                        .saturating_mul(10)
+                        .saturating_add(digit as u64);
+                }
+            }
+            TextField::Name(index) => {
+                let name = &mut settings.players[index].name;
+                if name.chars().count() < MAX_NAME_LENGTH {
+                    name.push(event.char);
+                }
+            }
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        match field {
+            TextField::Seed => settings.seed /= 10,
+            TextField::Name(index) => {
+                settings.players[index].name.pop();
+            }
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Return) {
+        edited_field.0 = None;
+    }
+}
+
+fn rebuild_setup_ui(
+    mut commands: Commands,
+    settings: Res<MatchSettings>,
+    edited_field: Res<EditedField>,
+    asset_server: Res<AssetServer>,
+    q_root: Query<Entity, With<MatchSetupRoot>>,
+) {
+    let root = q_root.get_single();
+    if root.is_ok() && !settings.is_changed() && !edited_field.is_changed() {
+        return;
+    }
+    if let Ok(root) = root {
+        commands.entity(root).despawn_recursive();
+    }
+
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+    let field_text = |text: String, field: TextField| {
+        if edited_field.0 == Some(field) {
+            format!("{text}_")
+        } else {
+            text
+        }
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            background_color: Color::BLACK.into(),
+            ..default()
+        })
+        .insert(MatchSetupRoot)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Match setup",
+                TextStyle {
+                    font_size: 40.0,
+                    ..text_style.clone()
+                },
+            ));
+
+            spawn_row(parent, |row| {
+                row.spawn(TextBundle::from_section("Galaxy:", text_style.clone()));
+                spawn_button(
+                    row,
+                    settings.preset.name(),
+                    SetupButton::Preset,
+                    &text_style,
+                );
+            });
+
+            spawn_row(parent, |row| {
+                row.spawn(TextBundle::from_section("Seed:", text_style.clone()));
+                spawn_button(
+                    row,
+                    field_text(settings.seed.to_string(), TextField::Seed),
+                    SetupButton::Seed,
+                    &text_style,
+                );
+                spawn_button(row, "Random", SetupButton::RandomSeed, &text_style);
+            });
+
+            spawn_row(parent, |row| {
+                row.spawn(TextBundle::from_section(
+                    format!("AI opponents: {}", settings.ai_count()),
+                    text_style.clone(),
+                ));
+                spawn_button(row, "-", SetupButton::RemoveAi, &text_style);
+                spawn_button(row, "+", SetupButton::AddAi, &text_style);
+            });
+
+            spawn_row(parent, |row| {
+                row.spawn(TextBundle::from_section("Colours:", text_style.clone()));
+                spawn_button(
+                    row,
+                    settings.palette.name(),
+                    SetupButton::Palette,
+                    &text_style,
+                );
+                spawn_button(
+                    row,
+                    match (settings.show_markers, settings.colors_repeat()) {
+                        (true, _) => "Markers: On",
+                        (false, true) => "Markers: On (colours repeat)",
+                        (false, false) => "Markers: Off",
+                    },
+                    SetupButton::Markers,
+                    &text_style,
+                );
+            });
+
+            for (index, player) in settings.players.iter().enumerate() {
+                spawn_row(parent, |row| {
+                    row.spawn(ButtonBundle {
+                        style: Style {
+                            width: Val::Px(24.0),
+                            height: Val::Px(24.0),
+                            ..default()
+                        },
+                        background_color: player.color.into(),
+                        ..default()
+                    })
+                    .insert(SetupButton::Color(index));
+
+                    spawn_button(
+                        row,
+                        field_text(player.name.clone(), TextField::Name(index)),
+                        SetupButton::Name(index),
+                        &text_style,
+                    );
+
+                    if !player.is_human {
+                        spawn_button(
+                            row,
+                            player.difficulty.name(),
+                            SetupButton::Difficulty(index),
+                            &text_style,
+                        );
+                    }
+                });
+            }
+
+            spawn_button(parent, "Start", SetupButton::Start, &text_style);
+        });
+}
+
+fn spawn_row(parent: &mut ChildBuilder, build: impl FnOnce(&mut ChildBuilder)) {
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                align_items: AlignItems::Center,
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+            ..default()
+        })
+        .with_children(build);
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    label: impl Into<String>,
+    button: SetupButton,
+    text_style: &TextStyle,
+) {
+    parent
+        .spawn(ButtonBundle {
+            style: Style {
+                padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                ..default()
+            },
+            background_color: BUTTON_COLOR.into(),
+            ..default()
+        })
+        .insert(button)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+}
+
+fn despawn_setup_ui(mut commands: Commands, q_root: Query<Entity, With<MatchSetupRoot>>) {
+    for root in q_root.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+}
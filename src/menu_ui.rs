@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use crate::{
+    app_state::{AppState, MatchResult},
+    match_settings::MatchSettings,
+    replay::{Replay, ReplayPlayback, WatchReplay},
+};
+
+const BUTTON_COLOR: Color = Color::rgb(0.15, 0.15, 0.25);
+const OVERLAY_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.7);
+
+pub struct MenuUiPlugin;
+
+#[derive(Component)]
+struct MenuRoot;
+
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+    NewGame,
+    Resume,
+    /// The same settings on a galaxy with a new seed.
+    PlayAgain,
+    /// The same settings and seed.
+    SameGalaxy,
+    WatchReplay,
+    MainMenu,
+}
+
+impl Plugin for MenuUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(AppState::MainMenu), spawn_main_menu)
+            .add_systems(OnEnter(AppState::Paused), spawn_pause_menu)
+            // Seeking back in a replay passes through the game over state
+            .add_systems(
+                OnEnter(AppState::GameOver),
+                spawn_game_over_menu.run_if(not(resource_exists::<ReplayPlayback>())),
+            )
+            .add_systems(OnExit(AppState::MainMenu), despawn_menu)
+            .add_systems(OnExit(AppState::Paused), despawn_menu)
+            .add_systems(OnExit(AppState::GameOver), despawn_menu)
+            .add_systems(Update, menu_button_pressed)
+            .add_systems(
+                Update,
+                toggle_pause
+                    .run_if(in_state(AppState::Playing).or_else(in_state(AppState::Paused))),
+            );
+    }
+}
+
+fn menu_button_pressed(
+    q_button: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut next_state: ResMut<NextState<AppState>>,
+    replay: Option<Res<Replay>>,
+    mut settings: ResMut<MatchSettings>,
+    mut commands: Commands,
+) {
+    for (interaction, &button) in q_button.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+
+        if let (MenuButton::WatchReplay, Some(replay)) = (button, &replay) {
+            commands.add(WatchReplay((**replay).clone()));
+        }
+        if let MenuButton::PlayAgain = button {
+            settings.seed = rand::random();
+        }
+        next_state.set(match button {
+            MenuButton::NewGame => AppState::MatchSetup,
+            MenuButton::Resume
+            | MenuButton::PlayAgain
+            | MenuButton::SameGalaxy
+            | MenuButton::WatchReplay => AppState::Playing,
+            MenuButton::MainMenu => AppState::MainMenu,
+        });
+    }
+}
+
+fn toggle_pause(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<State<AppState>>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    match state.get() {
+        AppState::Playing => next_state.set(AppState::Paused),
+        AppState::Paused => next_state.set(AppState::Playing),
+        _ => {}
+    }
+}
+
+fn spawn_main_menu(commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        commands,
+        &asset_server,
+        "Stars io",
+        Color::BLACK,
+        &[("New game", MenuButton::NewGame)],
+    );
+}
+
+fn spawn_pause_menu(commands: Commands, asset_server: Res<AssetServer>) {
+    spawn_menu(
+        commands,
+        &asset_server,
+        "Paused",
+        OVERLAY_COLOR,
+        &[
+            ("Resume", MenuButton::Resume),
+            ("Main menu", MenuButton::MainMenu),
+        ],
+    );
+}
+
+fn spawn_game_over_menu(
+    commands: Commands,
+    asset_server: Res<AssetServer>,
+    match_result: Res<MatchResult>,
+) {
+    let title = match *match_result {
+        MatchResult::Victory => "Victory!",
+        MatchResult::Defeat => "Defeat!",
+    };
+    spawn_menu(
+        commands,
+        &asset_server,
+        title,
+        OVERLAY_COLOR,
+        &[
+            ("Play again", MenuButton::PlayAgain),
+            ("Play same galaxy again", MenuButton::SameGalaxy),
+            ("Watch replay", MenuButton::WatchReplay),
+            ("Main menu", MenuButton::MainMenu),
+        ],
+    );
+}
+
+fn spawn_menu(
+    mut commands: Commands,
+    asset_server: &AssetServer,
+    title: &str,
+    background: Color,
+    buttons: &[(&str, MenuButton)],
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 24.0,
+        color: Color::WHITE,
+    };
+
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.0),
+                height: Val::Percent(100.0),
+                flex_direction: FlexDirection::Column,
+                align_items: AlignItems::Center,
+                justify_content: JustifyContent::Center,
+                row_gap: Val::Px(8.0),
+                ..default()
+            },
+            background_color: background.into(),
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .insert(MenuRoot)
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                title,
+                TextStyle {
+                    font_size: 40.0,
+                    ..text_style.clone()
+                },
+            ));
+
+            for &(label, button) in buttons {
+                parent
+                    .spawn(ButtonBundle {
+                        style: Style {
+                            padding: UiRect::axes(Val::Px(8.0), Val::Px(2.0)),
+                            ..default()
+                        },
+                        background_color: BUTTON_COLOR.into(),
+                        ..default()
+                    })
+                    .insert(button)
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(label, text_style.clone()));
+                    });
+            }
+        });
+}
+
+fn despawn_menu(mut commands: Commands, q_root: Query<Entity, With<MenuRoot>>) {
+    for root in q_root.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+}
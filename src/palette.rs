@@ -0,0 +1,134 @@
+use bevy::prelude::*;
+
+/// WCAG contrast for graphical objects, measured against the black band background.
+const MIN_CONTRAST: f32 = 3.0;
+
+/// Okabe-Ito palette, with white instead of black so it stays visible on the background.
+const COLOR_BLIND_SAFE: [Color; 8] = [
+    Color::rgb(0.902, 0.624, 0.0),
+    Color::rgb(0.337, 0.706, 0.914),
+    Color::rgb(0.0, 0.620, 0.451),
+    Color::rgb(0.941, 0.894, 0.259),
+    Color::rgb(0.0, 0.447, 0.698),
+    Color::rgb(0.835, 0.369, 0.0),
+    Color::rgb(0.800, 0.475, 0.655),
+    Color::rgb(1.0, 1.0, 1.0),
+];
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PaletteKind {
+    #[default]
+    Distinct,
+    ColorBlindSafe,
+}
+
+impl PaletteKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            PaletteKind::Distinct => "Distinct",
+            PaletteKind::ColorBlindSafe => "Colour-blind safe",
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            PaletteKind::Distinct => PaletteKind::ColorBlindSafe,
+            PaletteKind::ColorBlindSafe => PaletteKind::Distinct,
+        }
+    }
+
+    /// `count` colours in allocation order, each as far as possible from the ones before it.
+    /// Colours repeat once the palette runs out.
+    pub fn colors(self, count: usize) -> Vec<Color> {
+        let palette = match self {
+            PaletteKind::Distinct => distinct_colors(),
+            PaletteKind::ColorBlindSafe => COLOR_BLIND_SAFE.to_vec(),
+        };
+        palette.into_iter().cycle().take(count).collect()
+    }
+}
+
+/// Greedily picks the candidate furthest (in CIELAB) from every colour picked so far.
+fn distinct_colors() -> Vec<Color> {
+    let mut candidates: Vec<_> = (0..24)
+        .flat_map(|hue| {
+            [0.65, 0.95].into_iter().flat_map(move |saturation| {
+                [0.5, 0.65, 0.8]
+                    .into_iter()
+                    .map(move |lightness| Color::hsl(hue as f32 * 15.0, saturation, lightness))
+            })
+        })
+        .filter(|&color| contrast_with_black(color) >= MIN_CONTRAST)
+        .map(|color| (color, to_lab(color)))
+        .collect();
+
+    let mut picked = vec![candidates.remove(0)];
+    while !candidates.is_empty() {
+        let distance_to_picked = |lab: Vec3| {
+            picked
+                .iter()
+                .map(|(_, other)| lab.distance(*other))
+                .fold(f32::MAX, f32::min)
+        };
+        let (index, _) = candidates
+            .iter()
+            .enumerate()
+            .max_by(|(_, (_, a)), (_, (_, b))| {
+                distance_to_picked(*a).total_cmp(&distance_to_picked(*b))
+            })
+            .unwrap();
+        picked.push(candidates.remove(index));
+    }
+
+    picked.into_iter().map(|(color, _)| color).collect()
+}
+
+fn contrast_with_black(color: Color) -> f32 {
+    let [red, green, blue, _] = color.as_linear_rgba_f32();
+    let luminance = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+    (luminance + 0.05) / 0.05
+}
+
+fn to_lab(color: Color) -> Vec3 {
+    let [red, green, blue, _] = color.as_linear_rgba_f32();
+    let x = (0.4124 * red + 0.3576 * green + 0.1805 * blue) / 0.95047;
+    let y = 0.2126 * red + 0.7152 * green + 0.0722 * blue;
+    let z = (0.0193 * red + 0.1192 * green + 0.9505 * blue) / 1.08883;
+
+    let f = |t: f32| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    Vec3::new(116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+}
+
+/// Secondary shape drawn next to a player's stars, for players who can't tell colours apart.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerShape {
+    Circle,
+    Square,
+    Triangle,
+    Diamond,
+    Hexagon,
+}
+
+impl MarkerShape {
+    const ALL: [MarkerShape; 5] = [
+        MarkerShape::Circle,
+        MarkerShape::Square,
+        MarkerShape::Triangle,
+        MarkerShape::Diamond,
+        MarkerShape::Hexagon,
+    ];
+
+    /// Shapes repeat every 5 players and colour-blind safe colours every 8, so players given
+    /// palette colours in order never share both. Markers are only drawn when shown, see
+    /// `MatchSettings::markers_shown`.
+    pub fn for_player(index: usize) -> Self {
+        MarkerShape::ALL[index % MarkerShape::ALL.len()]
+    }
+}
@@ -1,7 +1,19 @@
 use bevy::prelude::*;
-use rand::Rng;
+use ctrl_macros::some_or_return;
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
 
-use crate::star_generation::Star;
+use crate::{
+    app_state::{AppState, MatchEntity, MatchResult, SimulationSet},
+    match_settings::MatchSettings,
+    palette::MarkerShape,
+    replay::ReplayPlayback,
+    star_generation::{Star, StarId, StartSlot},
+    start_placement::{choose_start_stars, StartCandidate},
+};
+
+/// Share of all owned stars the human has to own to win.
+const VICTORY_STAR_SHARE: f32 = 0.8;
 
 #[derive(Component)]
 pub struct Player {
@@ -10,29 +22,50 @@ pub struct Player {
     pub color: Color,
 }
 
+/// Position of the player in the match settings, the same in every run of a match.
+#[derive(
+    Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct PlayerId(pub u32);
+
 #[derive(Component)]
 pub struct OwnedBy {
     pub player: Entity,
 }
 
+/// Set once players have been placed on their start stars. A hand-built galaxy that already
+/// has its players sets it up front.
 #[derive(Resource)]
-struct GeneratedPlayers {
-    generated: bool,
+pub struct GeneratedPlayers {
+    pub generated: bool,
 }
 
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<MatchSettings>() {
+            app.insert_resource(MatchSettings::from_launch_args());
+        }
         app.insert_resource(GeneratedPlayers { generated: false })
-            .add_systems(Update, generate_players);
+            .add_systems(FixedUpdate, generate_players.in_set(SimulationSet::Setup))
+            // A replay keeps going until its recorded end
+            .add_systems(
+                FixedUpdate,
+                check_match_result
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .in_set(SimulationSet::Outcome),
+            )
+            .add_systems(OnEnter(AppState::MainMenu), reset_generated_players)
+            .add_systems(OnExit(AppState::GameOver), reset_generated_players);
     }
 }
 
 fn generate_players(
     mut commands: Commands,
-    star_query: Query<Entity, With<Star>>,
+    star_query: Query<(Entity, &StarId, &Star, &Transform, Option<&StartSlot>)>,
     mut generated_players: ResMut<GeneratedPlayers>,
+    settings: Res<MatchSettings>,
 ) {
     if generated_players.generated {
         return;
@@ -46,50 +79,88 @@ fn generate_players(
     info!("Stars count: {}", star_query.iter().count());
 
     generated_players.generated = true;
-    // let stars = star_query.iter() {
-
-    // }
-
-    for i in 0..10 {
-        let player = commands
-            .spawn_empty()
-            .insert(Player {
-                name: format!("AI: {}", i + 1),
-                is_human: false,
-                color: Color::Rgba {
-                    red: rand::thread_rng().gen_range(0.0..=1.0),
-                    green: rand::thread_rng().gen_range(0.0..=1.0),
-                    blue: rand::thread_rng().gen_range(0.0..=1.0),
-                    alpha: 1.0,
+    let mut rng = StdRng::seed_from_u64(settings.seed);
+
+    let players: Vec<_> = settings
+        .players
+        .iter()
+        .enumerate()
+        .map(|(index, player_settings)| {
+            let mut player = commands.spawn((
+                Player {
+                    name: player_settings.name.clone(),
+                    is_human: player_settings.is_human,
+                    color: player_settings.color,
                 },
-            })
-            .id();
-        assign_random_star_to_player(player, &star_query, &mut commands);
-    }
+                PlayerId(index as u32),
+                MatchEntity,
+            ));
+            if !player_settings.is_human {
+                player.insert(player_settings.difficulty);
+            }
+            if settings.markers_shown() {
+                player.insert(MarkerShape::for_player(index));
+            }
+            player.id()
+        })
+        .collect();
 
-    let player = commands
-        .spawn_empty()
-        .insert(Player {
-            name: "You".to_string(),
-            is_human: true,
-            color: Color::Rgba {
-                red: rand::thread_rng().gen_range(0.0..=1.0),
-                green: rand::thread_rng().gen_range(0.0..=1.0),
-                blue: rand::thread_rng().gen_range(0.0..=1.0),
-                alpha: 1.0,
+    let mut stars: Vec<_> = star_query.iter().collect();
+    stars.sort_by_key(|&(_, &star_id, _, _, _)| star_id);
+    let candidates: Vec<_> = stars
+        .into_iter()
+        .map(
+            |(star_entity, _, star, transform, start_slot)| StartCandidate {
+                star: star_entity,
+                position: transform.translation.truncate(),
+                size: star.size,
+                start_slot: start_slot.map(|start_slot| start_slot.0),
             },
-        })
-        .id();
+        )
+        .collect();
+    let start_stars = choose_start_stars(&candidates, players.len(), &mut rng);
 
-    assign_random_star_to_player(player, &star_query, &mut commands);
+    // Start slots end up on the stars the players started on, so an exported map starts each
+    // of them there again
+    for (star_entity, _, _, _, start_slot) in star_query.iter() {
+        if start_slot.is_some() {
+            commands.entity(star_entity).remove::<StartSlot>();
+        }
+    }
+    for (index, (player, star)) in players.into_iter().zip(start_stars).enumerate() {
+        commands
+            .entity(star)
+            .insert((OwnedBy { player }, StartSlot(index as u32)));
+    }
 }
 
-fn assign_random_star_to_player(
-    player: Entity,
-    star_query: &Query<Entity, With<Star>>,
-    commands: &mut Commands,
+fn reset_generated_players(mut generated_players: ResMut<GeneratedPlayers>) {
+    generated_players.generated = false;
+}
+
+fn check_match_result(
+    q_owned_star: Query<&OwnedBy, With<Star>>,
+    q_player: Query<(Entity, &Player)>,
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
 ) {
-    let random_star = rand::thread_rng().gen_range(0..star_query.iter().count());
-    let entity = star_query.iter().nth(random_star).unwrap();
-    commands.entity(entity).insert(OwnedBy { player });
+    let (human, _) = some_or_return!(q_player.iter().find(|(_, player)| player.is_human));
+
+    let total_stars = q_owned_star.iter().count();
+    let our_stars = q_owned_star
+        .iter()
+        .filter(|owned_by| owned_by.player == human)
+        .count();
+
+    let result = if our_stars == 0 {
+        MatchResult::Defeat
+    } else if our_stars as f32 > total_stars as f32 * VICTORY_STAR_SHARE {
+        MatchResult::Victory
+    } else {
+        return;
+    };
+
+    info!("Match over: {result:?}");
+    commands.insert_resource(result);
+    next_state.set(AppState::GameOver);
 }
@@ -0,0 +1,372 @@
+use std::{fs, path::Path};
+
+use bevy::{ecs::system::Command, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::{AppState, GameplaySet, SimulationSet},
+    galaxy_config::GalaxyConfig,
+    galaxy_map::GalaxyMap,
+    game_command::{apply_game_commands, GameCommand, GameCommandApplied},
+    launch_args::launch_arg,
+    match_settings::{Difficulty, MatchSettings, PlayerSettings},
+    simulation_clock::SimulationClock,
+};
+
+/// Ticks a seek runs per frame, so the window stays responsive while skipping far ahead.
+const MAX_SEEK_TICKS_PER_FRAME: u32 = 1200;
+
+/// Everything needed to play a match again: its setup and the commands applied in each tick.
+///
+/// The replay of the current match is recorded as it is played and written to
+/// `replay_<seed>.ron` when it ends.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub seed: u64,
+    pub galaxy: GalaxyConfig,
+    /// Map the match was played on instead of a generated galaxy.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub map: Option<GalaxyMap>,
+    pub players: Vec<ReplayPlayer>,
+    /// In the order they were applied.
+    pub commands: Vec<RecordedCommand>,
+    /// Tick the match ended at.
+    pub ticks: u64,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ReplayPlayer {
+    pub name: String,
+    pub is_human: bool,
+    pub color: [f32; 4],
+    pub difficulty: Difficulty,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RecordedCommand {
+    pub tick: u64,
+    pub command: GameCommand,
+}
+
+impl Replay {
+    pub fn new(settings: &MatchSettings, map: Option<GalaxyMap>) -> Self {
+        Replay {
+            seed: settings.seed,
+            galaxy: settings.galaxy.clone(),
+            map,
+            players: settings
+                .players
+                .iter()
+                .map(|player| ReplayPlayer {
+                    name: player.name.clone(),
+                    is_human: player.is_human,
+                    color: player.color.as_rgba_f32(),
+                    difficulty: player.difficulty,
+                })
+                .collect(),
+            commands: Vec::new(),
+            ticks: 0,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        ron::from_str(&contents).map_err(|err| err.to_string())
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), String> {
+        let contents = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|err| err.to_string())?;
+        fs::write(path, contents).map_err(|err| err.to_string())
+    }
+
+    /// Loads the replay given by the `replay` launch argument, if any.
+    pub fn from_launch_args() -> Option<Self> {
+        let path = launch_arg("replay")?;
+        match Replay::load(&path) {
+            Ok(replay) => Some(replay),
+            Err(err) => {
+                warn!("Failed to load replay {path}: {err}");
+                None
+            }
+        }
+    }
+
+    /// The commands applied in the given tick.
+    pub fn commands_at(&self, tick: u64) -> &[RecordedCommand] {
+        let start = self.commands.partition_point(|command| command.tick < tick);
+        let end = self
+            .commands
+            .partition_point(|command| command.tick <= tick);
+        &self.commands[start..end]
+    }
+}
+
+/// The replay being watched. While it exists, the match is re-simulated from the recorded
+/// commands instead of player input and the AI.
+#[derive(Resource)]
+pub struct ReplayPlayback {
+    pub replay: Replay,
+    seek_to: Option<u64>,
+    /// Whether the simulation was paused before seeking back restarted it.
+    resume_paused: Option<bool>,
+    /// Settings and map the replay replaced, restored when watching stops.
+    live_match: Option<(MatchSettings, Option<GalaxyMap>)>,
+}
+
+impl ReplayPlayback {
+    pub fn new(replay: Replay) -> Self {
+        ReplayPlayback {
+            replay,
+            seek_to: None,
+            resume_paused: None,
+            live_match: None,
+        }
+    }
+
+    /// Skips to the given tick, clamped to the end of the match. Seeking back plays the match
+    /// again from the start.
+    pub fn seek(&mut self, tick: u64) {
+        self.seek_to = Some(tick.min(self.replay.ticks));
+    }
+
+    pub fn seeking(&self) -> bool {
+        self.seek_to.is_some()
+    }
+}
+
+/// Sets up the replayed match and starts watching it with the next match started. The match
+/// settings and map are put back once watching stops.
+pub struct WatchReplay(pub Replay);
+
+impl Command for WatchReplay {
+    fn apply(self, world: &mut World) {
+        let replay = self.0;
+        let live_match = match world.get_resource::<ReplayPlayback>() {
+            // Watching another replay keeps the match from before the first one
+            Some(playback) => playback.live_match.clone(),
+            None => Some((
+                world.resource::<MatchSettings>().clone(),
+                world.get_resource::<GalaxyMap>().cloned(),
+            )),
+        };
+
+        let mut settings = world.resource_mut::<MatchSettings>();
+        settings.seed = replay.seed;
+        settings.galaxy = replay.galaxy.clone();
+        settings.players = replay
+            .players
+            .iter()
+            .map(|player| {
+                let [r, g, b, a] = player.color;
+                PlayerSettings {
+                    name: player.name.clone(),
+                    is_human: player.is_human,
+                    color: Color::rgba(r, g, b, a),
+                    difficulty: player.difficulty,
+                }
+            })
+            .collect();
+
+        match replay.map.clone() {
+            Some(map) => world.insert_resource(map),
+            None => {
+                world.remove_resource::<GalaxyMap>();
+            }
+        }
+        world.insert_resource(ReplayPlayback {
+            live_match,
+            ..ReplayPlayback::new(replay)
+        });
+    }
+}
+
+pub struct ReplayPlugin;
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, watch_replay_from_launch_args)
+            .add_systems(
+                OnTransition {
+                    from: AppState::MatchSetup,
+                    to: AppState::Playing,
+                },
+                start_recording.run_if(not(resource_exists::<ReplayPlayback>())),
+            )
+            .add_systems(
+                OnTransition {
+                    from: AppState::GameOver,
+                    to: AppState::Playing,
+                },
+                start_recording.run_if(not(resource_exists::<ReplayPlayback>())),
+            )
+            .add_systems(
+                FixedUpdate,
+                play_recorded_commands
+                    .run_if(resource_exists::<ReplayPlayback>())
+                    .in_set(SimulationSet::Setup),
+            )
+            .add_systems(
+                FixedUpdate,
+                record_game_commands
+                    .run_if(not(resource_exists::<ReplayPlayback>()))
+                    .after(apply_game_commands)
+                    .in_set(SimulationSet::Commands),
+            )
+            .add_systems(
+                OnEnter(AppState::GameOver),
+                save_replay.run_if(not(resource_exists::<ReplayPlayback>())),
+            )
+            .add_systems(
+                OnTransition {
+                    from: AppState::Paused,
+                    to: AppState::MainMenu,
+                },
+                (
+                    save_replay.run_if(not(resource_exists::<ReplayPlayback>())),
+                    stop_playback,
+                ),
+            )
+            .add_systems(
+                Update,
+                (rewind_replay, seek_replay, pause_at_replay_end)
+                    .chain()
+                    .run_if(resource_exists::<ReplayPlayback>())
+                    .in_set(GameplaySet),
+            )
+            .add_systems(
+                Update,
+                restart_replay.run_if(
+                    in_state(AppState::GameOver).and_then(resource_exists::<ReplayPlayback>()),
+                ),
+            );
+    }
+}
+
+fn watch_replay_from_launch_args(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if let Some(replay) = Replay::from_launch_args() {
+        commands.add(WatchReplay(replay));
+        next_state.set(AppState::Playing);
+    }
+}
+
+fn start_recording(
+    mut commands: Commands,
+    settings: Res<MatchSettings>,
+    galaxy_map: Option<Res<GalaxyMap>>,
+) {
+    let map = galaxy_map.map(|galaxy_map| galaxy_map.clone());
+    commands.insert_resource(Replay::new(&settings, map));
+}
+
+/// Records the commands applied in this tick. Dropped commands aren't recorded, so a replay
+/// only holds commands that are valid when played back.
+fn record_game_commands(
+    mut ev_applied: EventReader<GameCommandApplied>,
+    replay: Option<ResMut<Replay>>,
+    clock: Res<SimulationClock>,
+) {
+    let Some(mut replay) = replay else {
+        return;
+    };
+    for GameCommandApplied(command) in ev_applied.iter() {
+        replay.commands.push(RecordedCommand {
+            tick: clock.tick(),
+            command: command.clone(),
+        });
+    }
+}
+
+fn save_replay(replay: Option<ResMut<Replay>>, clock: Res<SimulationClock>) {
+    let Some(mut replay) = replay else {
+        return;
+    };
+    replay.ticks = clock.tick();
+
+    let path = format!("replay_{}.ron", replay.seed);
+    match replay.save(&path) {
+        Ok(()) => info!("Saved replay to {path}"),
+        Err(err) => warn!("Failed to save replay {path}: {err}"),
+    }
+}
+
+fn stop_playback(world: &mut World) {
+    let Some(playback) = world.remove_resource::<ReplayPlayback>() else {
+        return;
+    };
+    let Some((settings, map)) = playback.live_match else {
+        return;
+    };
+
+    world.insert_resource(settings);
+    match map {
+        Some(map) => world.insert_resource(map),
+        None => {
+            world.remove_resource::<GalaxyMap>();
+        }
+    }
+}
+
+fn play_recorded_commands(
+    playback: Res<ReplayPlayback>,
+    clock: Res<SimulationClock>,
+    mut game_commands: ResMut<Events<GameCommand>>,
+) {
+    for recorded in playback.replay.commands_at(clock.tick()) {
+        game_commands.send(recorded.command.clone());
+    }
+}
+
+/// Seeking back starts the match over through the game over state, like playing again does.
+fn rewind_replay(
+    mut playback: ResMut<ReplayPlayback>,
+    clock: Res<SimulationClock>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let Some(seek_to) = playback.seek_to else {
+        return;
+    };
+    if seek_to < clock.tick() {
+        playback.resume_paused = Some(clock.paused());
+        next_state.set(AppState::GameOver);
+    }
+}
+
+fn restart_replay(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::Playing);
+}
+
+/// Runs the ticks up to the seeked one back to back, a limited number per frame.
+fn seek_replay(world: &mut World) {
+    let Some(seek_to) = world.resource::<ReplayPlayback>().seek_to else {
+        return;
+    };
+    // Wait until a rewind has started the match over
+    if world.resource::<NextState<AppState>>().0.is_some() {
+        return;
+    }
+
+    if let Some(paused) = world.resource_mut::<ReplayPlayback>().resume_paused.take() {
+        let mut clock = world.resource_mut::<SimulationClock>();
+        if clock.paused() != paused {
+            clock.toggle_pause();
+        }
+    }
+
+    for _ in 0..MAX_SEEK_TICKS_PER_FRAME {
+        if world.resource::<SimulationClock>().tick() >= seek_to {
+            world.resource_mut::<ReplayPlayback>().seek_to = None;
+            break;
+        }
+        world.run_schedule(FixedUpdate);
+    }
+}
+
+fn pause_at_replay_end(playback: Res<ReplayPlayback>, mut clock: ResMut<SimulationClock>) {
+    if clock.tick() >= playback.replay.ticks && !clock.paused() {
+        clock.toggle_pause();
+    }
+}
@@ -0,0 +1,182 @@
+use bevy::{prelude::*, window::PrimaryWindow};
+use ctrl_macros::{ok_or_return, some_or_return};
+
+use crate::{
+    app_state::GameplaySet,
+    replay::ReplayPlayback,
+    simulation_clock::{SimulationClock, TICKS_PER_SECOND},
+};
+
+/// Ticks skipped by `[` and `]`.
+const SKIP_TICKS: u64 = 10 * TICKS_PER_SECOND;
+const TIMELINE_COLOR: Color = Color::rgba(0.15, 0.15, 0.25, 0.8);
+
+/// Timeline of the replay being watched. Clicking or dragging on it seeks.
+pub struct ReplayUiPlugin;
+
+#[derive(Component)]
+struct ReplayUiRoot;
+
+#[derive(Component)]
+struct ReplayText;
+
+#[derive(Component)]
+struct ReplayTimeline;
+
+/// Part of the timeline that has been played.
+#[derive(Component)]
+struct ReplayProgress;
+
+impl Plugin for ReplayUiPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            spawn_replay_ui.run_if(resource_added::<ReplayPlayback>()),
+        )
+        .add_systems(
+            Update,
+            despawn_replay_ui.run_if(resource_removed::<ReplayPlayback>()),
+        )
+        .add_systems(
+            Update,
+            (scrub_timeline, replay_hotkeys)
+                .run_if(resource_exists::<ReplayPlayback>())
+                .in_set(GameplaySet),
+        )
+        .add_systems(
+            Update,
+            update_replay_ui.run_if(resource_exists::<ReplayPlayback>()),
+        );
+    }
+}
+
+fn spawn_replay_ui(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(25.0),
+                width: Val::Percent(50.0),
+                bottom: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                ..default()
+            },
+            ..default()
+        })
+        .insert(ReplayUiRoot)
+        .with_children(|parent| {
+            parent
+                .spawn(TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                        font_size: 20.0,
+                        color: Color::WHITE,
+                    },
+                ))
+                .insert(ReplayText);
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(12.0),
+                        ..default()
+                    },
+                    background_color: TIMELINE_COLOR.into(),
+                    ..default()
+                })
+                .insert(Interaction::default())
+                .insert(ReplayTimeline)
+                .with_children(|parent| {
+                    parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                width: Val::Percent(0.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            background_color: Color::WHITE.into(),
+                            ..default()
+                        })
+                        .insert(ReplayProgress);
+                });
+        });
+}
+
+fn despawn_replay_ui(mut commands: Commands, q_root: Query<Entity, With<ReplayUiRoot>>) {
+    for root in q_root.iter() {
+        commands.entity(root).despawn_recursive();
+    }
+}
+
+/// Seeks to the point of the timeline under the cursor while it is pressed.
+fn scrub_timeline(
+    q_timeline: Query<(&Interaction, &Node, &GlobalTransform), With<ReplayTimeline>>,
+    q_window: Query<&Window, With<PrimaryWindow>>,
+    mut playback: ResMut<ReplayPlayback>,
+    clock: Res<SimulationClock>,
+) {
+    let window = ok_or_return!(q_window.get_single());
+    let cursor_position = some_or_return!(window.cursor_position());
+
+    for (interaction, node, transform) in q_timeline.iter() {
+        // Dragging seeks again once the previous seek is done
+        if *interaction != Interaction::Pressed || playback.seeking() {
+            continue;
+        }
+
+        let width = node.size().x;
+        let left = transform.translation().x - width / 2.0;
+        let fraction = ((cursor_position.x - left) / width).clamp(0.0, 1.0);
+        let tick = (fraction * playback.replay.ticks as f32) as u64;
+        if tick != clock.tick() {
+            playback.seek(tick);
+        }
+    }
+}
+
+/// `[` and `]` skip 10 seconds back and forth, Home goes back to the start.
+fn replay_hotkeys(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut playback: ResMut<ReplayPlayback>,
+    clock: Res<SimulationClock>,
+) {
+    if keyboard_input.just_pressed(KeyCode::BracketLeft) {
+        playback.seek(clock.tick().saturating_sub(SKIP_TICKS));
+    }
+    if keyboard_input.just_pressed(KeyCode::BracketRight) {
+        playback.seek(clock.tick() + SKIP_TICKS);
+    }
+    if keyboard_input.just_pressed(KeyCode::Home) {
+        playback.seek(0);
+    }
+}
+
+fn update_replay_ui(
+    playback: Res<ReplayPlayback>,
+    clock: Res<SimulationClock>,
+    mut q_text: Query<&mut Text, With<ReplayText>>,
+    mut q_progress: Query<&mut Style, With<ReplayProgress>>,
+) {
+    let end = playback.replay.ticks.max(1);
+    let progress = clock.tick().min(end) as f32 / end as f32;
+
+    for mut text in q_text.iter_mut() {
+        text.sections[0].value = format!(
+            "Replay {} / {} ([ ]: 10s back/forward, Home: start)",
+            format_ticks(clock.tick()),
+            format_ticks(playback.replay.ticks)
+        );
+    }
+    for mut style in q_progress.iter_mut() {
+        style.width = Val::Percent(progress * 100.0);
+    }
+}
+
+/// Game time of the tick as minutes and seconds.
+fn format_ticks(ticks: u64) -> String {
+    let seconds = ticks / TICKS_PER_SECOND;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
@@ -3,7 +3,10 @@ use bevy::{input::mouse::*, prelude::*, window::PrimaryWindow};
 use bevy_prototype_lyon::prelude::*;
 use ctrl_macros::{ok_or_return, some_or_return};
 
-use crate::top_down_camera::{screen_to_world, TopDownCamera};
+use crate::{
+    app_state::GameplaySet,
+    top_down_camera::{screen_to_world, TopDownCamera},
+};
 
 #[derive(Component)]
 pub struct Selectable {
@@ -40,8 +43,8 @@ impl Plugin for SelectionPlugin {
         app.add_event::<SelectionChanged>()
             .add_event::<OnSelected>()
             .insert_resource(SelectionRect::default())
-            .add_systems(Update, mouse_button_input)
-            .add_systems(Update, selection_changed);
+            .add_systems(Update, mouse_button_input.in_set(GameplaySet))
+            .add_systems(Update, selection_changed.in_set(GameplaySet));
     }
 }
 
@@ -6,6 +6,7 @@ use ctrl_macros::ok_or_continue;
 use crate::{
     players::{OwnedBy, Player},
     selection::*,
+    ship::Fleet,
 };
 
 #[derive(Component)]
@@ -26,6 +27,7 @@ fn mark_selected_with_rectangle(
     q_selectable: Query<(&Selectable, &Transform)>,
     q_player: Query<&Player>,
     q_owner: Query<&OwnedBy>,
+    q_fleet: Query<&Fleet>,
 
     mut commands: Commands,
 ) {
@@ -39,9 +41,14 @@ fn mark_selected_with_rectangle(
         }
 
         for &entity in event.entities.iter() {
-            let owner = ok_or_continue!(q_owner.get(entity));
+            // Stars are owned, fleets in flight belong to a player
+            let owner = q_owner
+                .get(entity)
+                .map(|owned_by| owned_by.player)
+                .or_else(|_| q_fleet.get(entity).map(|fleet| fleet.player));
+            let owner = ok_or_continue!(owner);
 
-            let player = ok_or_continue!(q_player.get(owner.player));
+            let player = ok_or_continue!(q_player.get(owner));
             if !player.is_human {
                 continue;
             }
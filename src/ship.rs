@@ -1,15 +1,22 @@
-use std::time::Duration;
-
-use bevy::{prelude::*, time::common_conditions::on_timer};
+use bevy::{ecs::system::SystemParam, prelude::*};
 use ctrl_macros::ok_or_continue;
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    players::{OwnedBy, Player},
-    star_generation::Star,
+    app_state::{AppState, MatchEntity, SimulationSet},
+    players::OwnedBy,
+    selection::Selectable,
+    simulation_clock::{every_n_ticks, TICKS_PER_SECOND, TICK_SECONDS},
+    star_generation::{Star, StarId},
 };
 
-const TWICE_PER_SECOND: f64 = 30.0 / 60.0;
-const FLY_TO_TIME_STEP: f64 = 1.0 / 60.0;
+const TWICE_PER_SECOND: u64 = TICKS_PER_SECOND / 2;
+/// Ships an owned star produces twice per second for each unit of its size.
+const SHIPS_PER_STAR_SIZE: f32 = 0.1;
+/// Opposing fleets in flight closer than this fight each other.
+const ENGAGEMENT_RADIUS: f32 = 10.0;
+/// Stars with a rally point send their ships on every two seconds.
+const RALLY_INTERVAL: u64 = 2 * TICKS_PER_SECOND;
 
 pub struct ShipPlugin;
 
@@ -30,98 +37,339 @@ pub struct FlyTo {
     pub destination_star: Entity,
 }
 
+/// Number of a launched fleet in launch order, the same in every run of a match.
+#[derive(
+    Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct FleetId(pub u32);
+
+/// The [`FleetId`] of the next launched fleet.
+#[derive(Resource, Default)]
+pub struct NextFleetId(u32);
+
+/// Star the ships produced at this star are sent on to, while both belong to `player`.
+#[derive(Component)]
+pub struct RallyPoint {
+    pub player: Entity,
+    pub star: Entity,
+}
+
+/// Distance a flying fleet covers per second of simulation time.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct FleetSpeed(pub f32);
+
+/// How fast fleets fly, so that small raiding fleets are faster than big armadas.
+#[derive(Resource, Clone, Debug)]
+pub struct FleetSpeedRules {
+    /// Speed of a fleet without ships, in units per second.
+    pub base_speed: f32,
+    /// Speed lost for every ship in the fleet.
+    pub slowdown_per_ship: f32,
+    /// No fleet is slower than this.
+    pub min_speed: f32,
+}
+
+impl Default for FleetSpeedRules {
+    fn default() -> Self {
+        Self {
+            base_speed: 105.0,
+            slowdown_per_ship: 1.0,
+            min_speed: 40.0,
+        }
+    }
+}
+
+impl FleetSpeedRules {
+    pub fn speed(&self, fleet_size: f32) -> f32 {
+        (self.base_speed - self.slowdown_per_ship * fleet_size).max(self.min_speed)
+    }
+
+    /// Seconds of simulation time a fleet of the given size needs to cover `distance`.
+    pub fn travel_seconds(&self, fleet_size: f32, distance: f32) -> f32 {
+        distance / self.speed(fleet_size)
+    }
+}
+
+/// Ships an owned star of the given size produces per second of simulation time.
+pub fn production_per_second(star_size: f32) -> f32 {
+    star_size * SHIPS_PER_STAR_SIZE * (TICKS_PER_SECOND / TWICE_PER_SECOND) as f32
+}
+
+/// Predicts when fleets in flight arrive at their destination.
+#[derive(SystemParam)]
+pub struct FleetArrival<'w, 's> {
+    q_fly_to: Query<
+        'w,
+        's,
+        (
+            &'static FlyTo,
+            &'static Fleet,
+            Option<&'static FleetSpeed>,
+            Option<&'static Transform>,
+        ),
+    >,
+    q_star: Query<'w, 's, &'static Transform, (With<Star>, Without<FlyTo>)>,
+    rules: Res<'w, FleetSpeedRules>,
+}
+
+impl FleetArrival<'_, '_> {
+    /// Seconds of simulation time until the fleet reaches its destination star, `None` if the
+    /// fleet isn't flying or its destination is gone.
+    pub fn seconds(&self, fleet: Entity) -> Option<f32> {
+        let (fly_to, fleet, fleet_speed, transform) = self.q_fly_to.get(fleet).ok()?;
+        let destination = self.q_star.get(fly_to.destination_star).ok()?;
+        // Fleets launched this tick get placed at their origin star in the next one
+        let position = match transform {
+            Some(transform) => transform.translation,
+            None => self.q_star.get(fly_to.origin_star).ok()?.translation,
+        };
+        let speed = fleet_speed.map_or_else(|| self.rules.speed(fleet.size), |speed| speed.0);
+
+        Some(position.distance(destination.translation) / speed)
+    }
+}
+
+/// How fleets arriving at a hostile star fight its defenders.
+#[derive(Resource, Clone, Debug)]
+pub struct CombatRules {
+    /// Each defending ship counts as `1 + defender_bonus * star size` attacking ships.
+    pub defender_bonus: f32,
+    /// Attacking ships per second that fight the defenders, so battles play out over several
+    /// ticks. `None` resolves a battle in the tick the attacker arrives.
+    pub battle_rate: Option<f32>,
+}
+
+impl Default for CombatRules {
+    fn default() -> Self {
+        Self {
+            defender_bonus: 0.05,
+            battle_rate: None,
+        }
+    }
+}
+
+impl CombatRules {
+    /// Attacking ships each defending ship at a star of the given size counts as.
+    pub fn defender_strength(&self, star_size: f32) -> f32 {
+        1.0 + self.defender_bonus * star_size
+    }
+}
+
+/// Losses so far of a fleet fighting the defenders of its destination star.
+#[derive(Component, Clone, Copy, Default)]
+pub struct Battle {
+    pub attacker_losses: f32,
+    pub defender_losses: f32,
+}
+
+/// Sent when a fleet arriving at a hostile star has captured it or has been destroyed.
+#[derive(Event, Clone, Debug)]
+pub struct CombatResolved {
+    pub star: Entity,
+    /// Player owning the attacking fleet.
+    pub attacker: Entity,
+    /// Player owning the star before the battle, if any.
+    pub defender: Option<Entity>,
+    pub attacker_losses: f32,
+    pub defender_losses: f32,
+    pub ownership_changed: bool,
+}
+
 impl Plugin for ShipPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update,
-            generate_ships_at_owned_stars
-                .run_if(on_timer(Duration::from_secs_f64(TWICE_PER_SECOND))),
+        app.add_systems(
+            FixedUpdate,
+            (
+                generate_new_ships_at_owned_stars,
+                generate_ships_at_owned_stars.run_if(every_n_ticks(TWICE_PER_SECOND)),
+                send_ships_to_rally_points.run_if(every_n_ticks(RALLY_INTERVAL)),
+            )
+                .chain()
+                .in_set(SimulationSet::Production),
+        )
+        .add_systems(
+            FixedUpdate,
+            (place_fly_to_ships, update_fleet_speed, fly_to)
+                .chain()
+                .in_set(SimulationSet::Movement),
         )
-        .add_systems(Update, generate_new_ships_at_owned_stars)
-        .add_systems(Update, generate_icon_for_fly_to_ships)
-        .add_systems(Update, fly_to.run_if(on_timer(Duration::from_secs_f64(FLY_TO_TIME_STEP))))
-        .add_systems(Update, fight)
-        .add_systems(Update, change_fleet_ownership);
+        .add_systems(
+            FixedUpdate,
+            (intercept_fleets, apply_deferred, fight)
+                .chain()
+                .in_set(SimulationSet::Combat),
+        )
+        .add_systems(OnEnter(AppState::MainMenu), reset_fleet_ids)
+        .add_systems(OnExit(AppState::GameOver), reset_fleet_ids)
+        .init_resource::<NextFleetId>()
+        .init_resource::<FleetSpeedRules>()
+        .init_resource::<CombatRules>()
+        .add_event::<CombatResolved>();
     }
 }
 
+/// Spawns a fleet of `size` ships of `player` flying from one star to another.
+pub fn launch_fleet(
+    commands: &mut Commands,
+    next_fleet_id: &mut NextFleetId,
+    player: Entity,
+    origin_star: Entity,
+    destination_star: Entity,
+    size: f32,
+) -> Entity {
+    let fleet_id = FleetId(next_fleet_id.0);
+    next_fleet_id.0 += 1;
+
+    commands
+        .spawn_empty()
+        .insert(Fleet { player, size })
+        .insert(FlyTo {
+            origin_star,
+            destination_star,
+        })
+        .insert(fleet_id)
+        .insert(MatchEntity)
+        .id()
+}
+
+fn reset_fleet_ids(mut next_fleet_id: ResMut<NextFleetId>) {
+    *next_fleet_id = NextFleetId::default();
+}
+
 fn generate_ships_at_owned_stars(
     query: Query<(&AttachedFleet, &Star)>,
     mut fleet_query: Query<&mut Fleet>,
 ) {
     for (attached_fleet, star) in query.iter() {
-        let mut fleet = fleet_query.get_mut(attached_fleet.fleet_id).unwrap();
-        fleet.size += star.size * 0.1;
+        let mut fleet = ok_or_continue!(fleet_query.get_mut(attached_fleet.fleet_id));
+        fleet.size += star.size * SHIPS_PER_STAR_SIZE;
     }
 }
 
 fn generate_new_ships_at_owned_stars(
     mut query: Query<(Entity, &OwnedBy), (With<Star>, Without<AttachedFleet>)>,
-    player_query: Query<&Player>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
 ) {
     for (entity, owned_by) in query.iter_mut() {
-        let player = ok_or_continue!(player_query.get(owned_by.player));
-
-        let fleet = commands
-            .spawn(SpriteBundle {
-                texture: asset_server.load("enemy_E.png"),
-                transform: Transform::from_xyz(10.0, 10.0, 0.0),
-                sprite: Sprite {
-                    color: player.color,
-                    custom_size: Some(Vec2::new(10.0, 10.0)),
-                    ..default()
-                },
-                ..default()
-            })
-            .insert(Fleet {
-                player: owned_by.player,
-                size: 0.0,
-            })
-            .id();
-
-        commands.entity(entity).push_children(&[fleet]);
-        commands
-            .entity(entity)
-            .insert(AttachedFleet { fleet_id: fleet });
-    }
-}
-
-fn generate_icon_for_fly_to_ships(
-    query: Query<(Entity, &Fleet, &FlyTo), Added<FlyTo>>,
-    q_origin: Query<&Transform>,
-    q_player: Query<&Player>,
+        spawn_star_fleet(&mut commands, entity, owned_by.player, 0.0);
+    }
+}
+
+/// Spawns the fleet stationed at a star.
+fn spawn_star_fleet(commands: &mut Commands, star: Entity, player: Entity, size: f32) {
+    let fleet = commands
+        .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+            10.0, 10.0, 0.0,
+        )))
+        .insert(Fleet { player, size })
+        .id();
+
+    commands.entity(star).push_children(&[fleet]);
+    commands
+        .entity(star)
+        .insert(AttachedFleet { fleet_id: fleet });
+}
+
+/// Stars send all their ships to their rally point. Rally points of stars or rally stars that
+/// changed owner are dropped.
+fn send_ships_to_rally_points(
+    query: Query<(Entity, &RallyPoint, &OwnedBy, &AttachedFleet, &StarId)>,
+    q_owner: Query<&OwnedBy>,
+    mut q_fleet: Query<&mut Fleet>,
+    mut next_fleet_id: ResMut<NextFleetId>,
     mut commands: Commands,
-    asset_server: Res<AssetServer>,
 ) {
-    for (entity, fleet, fly_to) in query.iter() {
-        let player = ok_or_continue!(q_player.get(fleet.player));
+    let mut stars: Vec<_> = query.iter().collect();
+    stars.sort_by_key(|&(.., &star_id)| star_id);
 
-        let transform = *q_origin.get(fly_to.origin_star).unwrap();
+    for (entity, rally_point, owned_by, attached_fleet, _) in stars {
+        let rally_owner = q_owner.get(rally_point.star).ok();
+        if owned_by.player != rally_point.player
+            || rally_owner.map(|owned_by| owned_by.player) != Some(rally_point.player)
+        {
+            commands.entity(entity).remove::<RallyPoint>();
+            continue;
+        }
+
+        let mut fleet = ok_or_continue!(q_fleet.get_mut(attached_fleet.fleet_id));
+        if fleet.size <= 0.0 || rally_point.star == entity {
+            continue;
+        }
+        launch_fleet(
+            &mut commands,
+            &mut next_fleet_id,
+            fleet.player,
+            entity,
+            rally_point.star,
+            fleet.size,
+        );
+        fleet.size = 0.0;
+    }
+}
+
+/// Launched fleets start at their origin star and can be selected to change their destination.
+/// Fleets launched from a star that no longer exists are dropped.
+fn place_fly_to_ships(
+    query: Query<(Entity, &FlyTo, &Fleet), Added<FlyTo>>,
+    q_origin: Query<&Transform>,
+    rules: Res<FleetSpeedRules>,
+    mut commands: Commands,
+) {
+    for (entity, fly_to, fleet) in query.iter() {
+        let Ok(&transform) = q_origin.get(fly_to.origin_star) else {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        };
 
-        commands.entity(entity).insert(SpriteBundle {
-            texture: asset_server.load("enemy_E.png"),
-            sprite: Sprite {
-                color: player.color,
-                custom_size: Some(Vec2::new(10.0, 10.0)),
-                ..default()
+        commands.entity(entity).insert((
+            SpatialBundle::from_transform(transform),
+            FleetSpeed(rules.speed(fleet.size)),
+            Selectable {
+                width: 14.0,
+                height: 14.0,
             },
-            transform,
-            ..default()
-        });
+        ));
+    }
+}
+
+/// Fleets that lost ships in flight speed up.
+fn update_fleet_speed(
+    mut query: Query<(&Fleet, &mut FleetSpeed), Changed<Fleet>>,
+    rules: Res<FleetSpeedRules>,
+) {
+    for (fleet, mut fleet_speed) in query.iter_mut() {
+        let speed = rules.speed(fleet.size);
+        if fleet_speed.0 != speed {
+            fleet_speed.0 = speed;
+        }
     }
 }
 
 fn fly_to(
-    mut q_fly_to: Query<(&FlyTo, &mut Transform)>,
-    q_destination: Query<&Transform, Without<FlyTo>>,
+    mut q_fly_to: Query<(Entity, &mut FlyTo, &FleetSpeed, &mut Transform)>,
+    q_destination: Query<&Transform, (With<Star>, Without<FlyTo>)>,
+    mut commands: Commands,
 ) {
-    for (fly_to, mut transform) in q_fly_to.iter_mut() {
-        let destination_transform = q_destination.get(fly_to.destination_star).unwrap();
-        let delta = (destination_transform.translation - transform.translation).normalize()
-            * (FLY_TO_TIME_STEP as f32)
-            * 100.0;
-        transform.translation += delta;
+    for (entity, mut fly_to, fleet_speed, mut transform) in q_fly_to.iter_mut() {
+        let Ok(destination_transform) = q_destination.get(fly_to.destination_star) else {
+            // The destination is gone, return home or stay put if home is gone too
+            if fly_to.destination_star != fly_to.origin_star
+                && q_destination.contains(fly_to.origin_star)
+            {
+                fly_to.destination_star = fly_to.origin_star;
+            } else {
+                commands.entity(entity).remove::<FlyTo>();
+            }
+            continue;
+        };
+        let step = fleet_speed.0 * TICK_SECONDS;
+        let to_destination = destination_transform.translation - transform.translation;
+        // Don't overshoot the destination
+        if to_destination.length() <= step {
+            transform.translation = destination_transform.translation;
+        } else {
+            transform.translation += to_destination.normalize() * step;
+        }
 
         // TODO: figure out proper rotation later
 
@@ -132,8 +380,77 @@ fn fly_to(
     }
 }
 
+/// Key that orders fleets in flight the same way in every run of a match. Fleets of the same
+/// size on the same route are told apart by their [`FleetId`].
+fn fleet_order(
+    entity: Entity,
+    fly_to: &FlyTo,
+    fleet: &Fleet,
+    q_star_id: &Query<&StarId>,
+    q_fleet_id: &Query<&FleetId>,
+) -> (Option<StarId>, Option<StarId>, u32, Option<FleetId>) {
+    (
+        q_star_id.get(fly_to.origin_star).ok().copied(),
+        q_star_id.get(fly_to.destination_star).ok().copied(),
+        fleet.size.to_bits(),
+        q_fleet_id.get(entity).ok().copied(),
+    )
+}
+
+/// Opposing fleets that meet in flight fight like at a star: the smaller fleet is destroyed and
+/// the larger one continues with the difference. Fleets fighting at a star only lose ships in
+/// that battle.
+fn intercept_fleets(
+    mut q_fly_to: Query<(Entity, &FlyTo, &mut Fleet, &Transform), Without<Battle>>,
+    q_star_id: Query<&StarId>,
+    q_fleet_id: Query<&FleetId>,
+    mut commands: Commands,
+) {
+    let mut fleets: Vec<_> = q_fly_to
+        .iter()
+        .map(|(entity, fly_to, fleet, transform)| {
+            (
+                fleet_order(entity, fly_to, &fleet, &q_star_id, &q_fleet_id),
+                entity,
+                fleet.player,
+                fleet.size,
+                transform.translation.truncate(),
+            )
+        })
+        .collect();
+    fleets.sort_by_key(|&(order, ..)| order);
+
+    for i in 0..fleets.len() {
+        for j in i + 1..fleets.len() {
+            let (_, _, player, size, position) = fleets[i];
+            let (_, _, other_player, other_size, other_position) = fleets[j];
+            if size <= 0.0 || other_size <= 0.0 || player == other_player {
+                continue;
+            }
+            if position.distance(other_position) > ENGAGEMENT_RADIUS {
+                continue;
+            }
+
+            fleets[i].3 = (size - other_size).max(0.0);
+            fleets[j].3 = (other_size - size).max(0.0);
+        }
+    }
+
+    for (_, entity, _, size, _) in fleets {
+        if size <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            let (_, _, mut fleet, _) = ok_or_continue!(q_fly_to.get_mut(entity));
+            if fleet.size != size {
+                fleet.size = size;
+            }
+        }
+    }
+}
+
+/// Fleets arriving at a star reinforce it if it's their own and fight its defenders otherwise.
 fn fight(
-    q_fly_to: Query<(Entity, &FlyTo, &Fleet, &Transform)>,
+    mut q_fly_to: Query<(Entity, &FlyTo, &mut Fleet, &Transform, Option<&mut Battle>)>,
     mut q_destination: Query<
         (
             &Transform,
@@ -144,47 +461,136 @@ fn fight(
         Without<FlyTo>,
     >,
     mut q_destination_fleet: Query<&mut Fleet, Without<FlyTo>>,
+    q_star_id: Query<&StarId>,
+    q_fleet_id: Query<&FleetId>,
+    rules: Res<CombatRules>,
+    mut combat_resolved: EventWriter<CombatResolved>,
     mut commands: Commands,
 ) {
-    for (entity, fly_to, fleet, transform) in q_fly_to.iter() {
-        let (destination_transform, _, attached_fleet, owned_by) =
-            q_destination.get_mut(fly_to.destination_star).unwrap();
+    // Fleets arriving at the same star in the same tick fight in a fixed order
+    let mut fleets: Vec<_> = q_fly_to
+        .iter()
+        .map(|(entity, fly_to, fleet, _, _)| {
+            let order = fleet_order(entity, fly_to, fleet, &q_star_id, &q_fleet_id);
+            (order, entity)
+        })
+        .collect();
+    fleets.sort_by_key(|&(order, _)| order);
+    // Ships that become the fleet of a star without one, spawned once every fleet arrived
+    let mut new_star_fleets: Vec<(Entity, Entity, f32)> = Vec::new();
+
+    for (_, entity) in fleets {
+        let (_, fly_to, mut fleet, transform, battle) = ok_or_continue!(q_fly_to.get_mut(entity));
+        let star_entity = fly_to.destination_star;
+        let (destination_transform, star, attached_fleet, owned_by) =
+            ok_or_continue!(q_destination.get_mut(star_entity));
         let distance = transform
             .translation
             .distance(destination_transform.translation);
-        if distance < 1.0 {
-            if let Some(attached_fleet) = attached_fleet {
-                let mut target_fleet = q_destination_fleet
-                    .get_mut(attached_fleet.fleet_id)
-                    .unwrap();
-                if target_fleet.player != fleet.player {
-                    target_fleet.size -= fleet.size;
-                } else {
-                    target_fleet.size += fleet.size;
+        if distance >= 1.0 {
+            continue;
+        }
+
+        let mut losses = battle.as_deref().copied().unwrap_or_default();
+        let target_fleet = attached_fleet
+            .and_then(|attached_fleet| q_destination_fleet.get_mut(attached_fleet.fleet_id).ok());
+        let Some(mut target_fleet) = target_fleet else {
+            commands.entity(entity).despawn_recursive();
+            // Changes to the star from earlier arrivals in this tick aren't applied yet
+            let pending = new_star_fleets
+                .iter()
+                .position(|&(star, ..)| star == star_entity);
+            let defender = match pending {
+                Some(index) => Some(new_star_fleets[index].1),
+                None => owned_by.map(|owned_by| owned_by.player),
+            };
+
+            // Reinforcements for a star whose fleet is gone stay there as its fleet
+            if defender == Some(fleet.player) {
+                match pending {
+                    Some(index) => new_star_fleets[index].2 += fleet.size,
+                    None => new_star_fleets.push((star_entity, fleet.player, fleet.size)),
                 }
-                if target_fleet.size < 0.0 {
-                    owned_by.unwrap().player = fleet.player;
-                    target_fleet.player = fleet.player;
-                    target_fleet.size *= -1.0;
-                };
-            } else {
-                commands.entity(fly_to.destination_star).insert(OwnedBy {
+                continue;
+            }
+
+            combat_resolved.send(CombatResolved {
+                star: star_entity,
+                attacker: fleet.player,
+                defender,
+                attacker_losses: losses.attacker_losses,
+                defender_losses: losses.defender_losses,
+                ownership_changed: true,
+            });
+            commands
+                .entity(star_entity)
+                .insert(OwnedBy {
                     player: fleet.player,
-                });
-            };
+                })
+                .remove::<AttachedFleet>();
+            if let Some(index) = pending {
+                new_star_fleets.remove(index);
+            }
+            // Unowned stars keep the ships that took them, a star whose fleet is gone gets a new
+            // one generated for the new owner
+            if defender.is_none() {
+                new_star_fleets.push((star_entity, fleet.player, fleet.size));
+            }
+            continue;
+        };
 
-            commands.entity(entity).despawn();
+        if target_fleet.player == fleet.player {
+            target_fleet.size += fleet.size;
+            commands.entity(entity).despawn_recursive();
+            continue;
         }
-    }
-}
 
-fn change_fleet_ownership(
-    mut query: Query<(&mut Sprite, &Fleet), Changed<Fleet>>,
-    q_player: Query<&Player>,
-) {
-    for (mut sprite, fleet) in query.iter_mut() {
-        let player = ok_or_continue!(q_player.get(fleet.player));
+        // Defenders are stronger at bigger stars
+        let strength = rules.defender_strength(star.size);
+        let defence = target_fleet.size * strength;
+        let mut exchanged = fleet.size.min(defence);
+        if let Some(battle_rate) = rules.battle_rate {
+            exchanged = exchanged.min(battle_rate * TICK_SECONDS);
+        }
+        losses.attacker_losses += exchanged;
+        losses.defender_losses += exchanged / strength;
+        fleet.size -= exchanged;
+        if exchanged >= defence {
+            target_fleet.size = 0.0;
+        } else {
+            target_fleet.size -= exchanged / strength;
+        }
+
+        // A draw leaves the star with its owner
+        let captured = target_fleet.size <= 0.0 && fleet.size > 0.0;
+        if captured {
+            if let Some(mut owned_by) = owned_by {
+                owned_by.player = fleet.player;
+            }
+            target_fleet.size = fleet.size;
+        }
+
+        if captured || fleet.size <= 0.0 {
+            combat_resolved.send(CombatResolved {
+                star: star_entity,
+                attacker: fleet.player,
+                defender: Some(target_fleet.player),
+                attacker_losses: losses.attacker_losses,
+                defender_losses: losses.defender_losses,
+                ownership_changed: captured,
+            });
+            if captured {
+                target_fleet.player = fleet.player;
+            }
+            commands.entity(entity).despawn_recursive();
+        } else if let Some(mut battle) = battle {
+            *battle = losses;
+        } else {
+            commands.entity(entity).insert(losses);
+        }
+    }
 
-        sprite.color = player.color;
+    for (star, player, size) in new_star_fleets {
+        spawn_star_fleet(&mut commands, star, player, size);
     }
 }
@@ -0,0 +1,130 @@
+use bevy::prelude::*;
+
+use crate::app_state::{AppState, GameplaySet};
+
+/// Simulation ticks run in `FixedUpdate` per second of game time.
+pub const TICKS_PER_SECOND: u64 = 60;
+/// Game time covered by a single tick.
+pub const TICK_SECONDS: f32 = 1.0 / TICKS_PER_SECOND as f32;
+
+/// Selectable simulation speeds.
+const SPEEDS: [f32; 4] = [0.5, 1.0, 2.0, 4.0];
+const DEFAULT_SPEED_INDEX: usize = 1;
+
+/// Pause state, speed and tick counter of the running match.
+///
+/// The simulation advances in fixed ticks, so a match replayed from the same seed and commands
+/// ends up in the same state regardless of frame rate or speed.
+#[derive(Resource)]
+pub struct SimulationClock {
+    paused: bool,
+    speed_index: usize,
+    step_requested: bool,
+    tick: u64,
+}
+
+impl Default for SimulationClock {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            speed_index: DEFAULT_SPEED_INDEX,
+            step_requested: false,
+            tick: 0,
+        }
+    }
+}
+
+impl SimulationClock {
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn speed(&self) -> f32 {
+        SPEEDS[self.speed_index]
+    }
+
+    /// Number of simulation ticks run since the match started.
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+    }
+
+    pub fn faster(&mut self) {
+        self.speed_index = (self.speed_index + 1).min(SPEEDS.len() - 1);
+    }
+
+    pub fn slower(&mut self) {
+        self.speed_index = self.speed_index.saturating_sub(1);
+    }
+
+    /// Runs a single tick of a paused simulation on the next frame.
+    pub fn step(&mut self) {
+        if self.paused {
+            self.step_requested = true;
+        }
+    }
+
+    fn reset(&mut self) {
+        *self = Self {
+            speed_index: self.speed_index,
+            ..default()
+        };
+    }
+}
+
+/// Runs a system every `interval` simulation ticks.
+pub fn every_n_ticks(interval: u64) -> impl Fn(Res<SimulationClock>) -> bool + Clone {
+    move |clock: Res<SimulationClock>| clock.tick % interval == 0
+}
+
+pub struct SimulationClockPlugin;
+
+impl Plugin for SimulationClockPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SimulationClock>()
+            .insert_resource(FixedTime::new_from_secs(TICK_SECONDS))
+            .add_systems(PreUpdate, apply_simulation_speed)
+            .add_systems(
+                FixedUpdate,
+                advance_tick
+                    .run_if(in_state(AppState::Playing))
+                    .before(GameplaySet),
+            )
+            .add_systems(OnEnter(AppState::MainMenu), reset_simulation_clock)
+            .add_systems(OnExit(AppState::GameOver), reset_simulation_clock);
+    }
+}
+
+/// `FixedUpdate` runs as often as the scaled [`Time`] allows, so pausing and speed are applied
+/// to it. Real time stays available as [`Time::raw_delta`] for input and camera movement.
+fn apply_simulation_speed(
+    mut clock: ResMut<SimulationClock>,
+    mut time: ResMut<Time>,
+    mut fixed_time: ResMut<FixedTime>,
+) {
+    if clock.paused && !time.is_paused() {
+        time.pause();
+    } else if !clock.paused && time.is_paused() {
+        time.unpause();
+    }
+    if time.relative_speed() != clock.speed() {
+        time.set_relative_speed(clock.speed());
+    }
+
+    if clock.step_requested {
+        clock.step_requested = false;
+        let period = fixed_time.period;
+        fixed_time.tick(period);
+    }
+}
+
+fn advance_tick(mut clock: ResMut<SimulationClock>) {
+    clock.tick += 1;
+}
+
+fn reset_simulation_clock(mut clock: ResMut<SimulationClock>) {
+    clock.reset();
+}
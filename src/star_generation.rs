@@ -1,34 +1,69 @@
-use std::f32::consts::PI;
-
 use bevy::prelude::*;
-use bevy_prototype_lyon::prelude::*;
-use rand::Rng;
-use rand_distr::{Distribution, Normal};
-
-use crate::selection::Selectable;
-
-const BAND_SIZE_MIN: f32 = 170.0;
-const BAND_SIZE_MAX: f32 = 200.0;
-const BAND_Z_INDEX_START: f32 = -10.0;
-const EMPTY_AREA_SIZE_MIN: f32 = 250.0;
-const EMPTY_AREA_SIZE_MAX: f32 = 500.0;
-const MIN_STARS_IN_CLUSTER: u32 = 1;
-const MAX_STARS_IN_CLUSTER: u32 = 5;
-const STAR_SIZE_MEAN: f32 = 2.0;
-const STAR_SIZE_DEVIATION: f32 = 2.0;
-
-#[derive(Component)]
-struct Band {
-    // index: i32,
-    cluster_count: i32,
-    distance_from_center: f32,
-    size: f32,
+use rand::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    app_state::{AppState, MatchEntity},
+    galaxy_layout::Band,
+    galaxy_map::GalaxyMap,
+    launch_args::launch_arg,
+    match_settings::MatchSettings,
+    selection::Selectable,
+};
+
+pub const BAND_Z_INDEX_START: f32 = -10.0;
+// Star labels are drawn above and below the star, see `game_ui` and `incoming_ui`. They are 8px
+// text (a 40px font scaled by 0.2) in rows about 10px high.
+const STAR_LABEL_FONT_SIZE: f32 = 8.0;
+const STAR_LABEL_ROW_HEIGHT: f32 = 10.0;
+/// Average advance of a FiraSans Bold glyph, relative to the font size.
+const STAR_LABEL_GLYPH_WIDTH: f32 = 0.55;
+/// The longest label is the resource label, "M: xx.xx  F: yyy.yy".
+const STAR_LABEL_MAX_CHARS: f32 = 19.0;
+const STAR_LABEL_HALF_WIDTH: f32 =
+    STAR_LABEL_MAX_CHARS * STAR_LABEL_FONT_SIZE * STAR_LABEL_GLYPH_WIDTH * 0.5;
+/// The first row of incoming fleets sits on top of the resource label, which ends 20px above
+/// the star. The player name below the star doesn't reach as far.
+const STAR_LABEL_HALF_HEIGHT: f32 = 20.0 + STAR_LABEL_ROW_HEIGHT;
+
+/// Seed for the single RNG that drives galaxy generation, so a map can be shared and reproduced.
+#[derive(Resource, Clone, Copy)]
+pub struct GalaxySeed(pub u64);
+
+impl GalaxySeed {
+    /// Uses the `seed` launch argument if given, otherwise picks a random seed.
+    pub fn from_launch_args() -> Self {
+        let seed = launch_arg("seed").and_then(|seed| seed.parse().ok());
+        GalaxySeed(seed.unwrap_or_else(rand::random))
+    }
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct NewStar {
-    x: f32,
-    y: f32,
-    size: f32,
+    pub x: f32,
+    pub y: f32,
+    pub size: f32,
+    /// Index of the player that starts on this star, for hand-authored maps.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start_slot: Option<u32>,
+}
+
+impl NewStar {
+    /// Area covered by the star's sprite and its labels.
+    pub fn footprint(&self) -> Rect {
+        let sprite_half_size = star_sprite_size(self.size) * 0.5;
+        Rect::from_center_half_size(
+            Vec2::new(self.x, self.y),
+            Vec2::new(
+                sprite_half_size.max(STAR_LABEL_HALF_WIDTH),
+                sprite_half_size.max(STAR_LABEL_HALF_HEIGHT),
+            ),
+        )
+    }
+
+    pub fn overlaps(&self, other: &NewStar) -> bool {
+        !self.footprint().intersect(other.footprint()).is_empty()
+    }
 }
 
 #[derive(Component)]
@@ -36,172 +71,108 @@ pub struct Star {
     pub size: f32,
 }
 
+#[derive(Component)]
+pub struct StartSlot(pub u32);
+
+/// Position of the star in generation order. Unlike the `Entity`, it is the same every time
+/// a galaxy is generated from the same seed.
+#[derive(
+    Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+pub struct StarId(pub u32);
+
 pub struct StarGenerationPlugin;
 
 impl Plugin for StarGenerationPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, generate_bands);
+        if !app.world.contains_resource::<MatchSettings>() {
+            app.insert_resource(MatchSettings::from_launch_args());
+        }
+        if !app.world.contains_resource::<GalaxyMap>() {
+            if let Some(galaxy_map) = GalaxyMap::from_launch_args() {
+                app.insert_resource(galaxy_map);
+            }
+        }
+        app.add_systems(
+            OnTransition {
+                from: AppState::MatchSetup,
+                to: AppState::Playing,
+            },
+            generate_galaxy,
+        )
+        .add_systems(
+            OnTransition {
+                from: AppState::GameOver,
+                to: AppState::Playing,
+            },
+            generate_galaxy,
+        )
+        // Replays given at launch start straight from the main menu
+        .add_systems(
+            OnTransition {
+                from: AppState::MainMenu,
+                to: AppState::Playing,
+            },
+            generate_galaxy,
+        );
     }
 }
 
-fn generate_bands(mut commands: Commands, asset_server: Res<AssetServer>) {
-    info!("Generate bands!");
-    // Empty area between bands
-    let size = rand::thread_rng().gen_range(EMPTY_AREA_SIZE_MIN..=EMPTY_AREA_SIZE_MAX);
-    let mut band_size_total = size;
+fn generate_galaxy(
+    mut commands: Commands,
+    settings: Res<MatchSettings>,
+    galaxy_map: Option<Res<GalaxyMap>>,
+) {
+    info!("Generate galaxy! Seed: {}", settings.seed);
+    let mut rng = StdRng::seed_from_u64(settings.seed);
 
-    let shape = shapes::Circle {
-        radius: band_size_total,
-        ..shapes::Circle::default()
+    let config = settings.galaxy.clone();
+    let galaxy = match galaxy_map {
+        Some(galaxy_map) => galaxy_map.clone(),
+        None => config.layout.layout().generate(&config, &mut rng),
     };
-    commands.spawn((
-        ShapeBundle {
-            path: GeometryBuilder::build_as(&shape),
-            transform: Transform::from_xyz(0.0, 0.0, BAND_Z_INDEX_START + 1.0),
-            ..default()
-        },
-        Fill::color(Color::Rgba {
-            red: 0.0,
-            green: 0.0,
-            blue: 0.0,
-            alpha: 1.0,
-        }),
-    ));
-
-    for index in 0..5 {
-        let size = rand::thread_rng().gen_range(BAND_SIZE_MIN..=BAND_SIZE_MAX);
-        let distance_from_center = band_size_total;
-        band_size_total += size;
-
-        // Band
-        let shape = shapes::Circle {
-            radius: distance_from_center + size,
-            ..shapes::Circle::default()
-        };
-
-        let cluster_count = ((index + 1) as f32 * 2.0 * PI) as i32;
-
-        let band = Band {
-            // index,
-            distance_from_center,
-            size,
-            cluster_count,
-        };
-        generate_clusters_for_band(&band, &mut commands, &asset_server);
-        commands
-            .spawn((
-                ShapeBundle {
-                    path: GeometryBuilder::build_as(&shape),
-                    transform: Transform::from_xyz(
-                        0.0,
-                        0.0,
-                        BAND_Z_INDEX_START - (index as f32) * 2.0,
-                    ),
-                    ..default()
-                },
-                Fill::color(Color::Rgba {
-                    red: rand::thread_rng().gen_range(0.0..=1.0),
-                    green: rand::thread_rng().gen_range(0.0..=1.0),
-                    blue: rand::thread_rng().gen_range(0.0..=1.0),
-                    // alpha: 1.0,
-                    alpha: 0.0,
-                }),
-            ))
-            .insert(band);
-
-        // Empty area between bands
-        let size = rand::thread_rng().gen_range(EMPTY_AREA_SIZE_MIN..EMPTY_AREA_SIZE_MAX);
-        band_size_total += size;
-
-        let shape = shapes::Circle {
-            radius: band_size_total,
-            ..shapes::Circle::default()
-        };
-        commands.spawn((
-            ShapeBundle {
-                path: GeometryBuilder::build_as(&shape),
-                transform: Transform::from_xyz(
-                    0.0,
-                    0.0,
-                    BAND_Z_INDEX_START - (index as f32) * 2.0 - 1.0,
-                ),
-                ..default()
-            },
-            Fill::color(Color::Rgba {
-                red: 0.0,
-                green: 0.0,
-                blue: 0.0,
-                // alpha: 1.0,
-                alpha: 0.0,
-            }),
-        ));
+    commands.insert_resource(GalaxySeed(settings.seed));
+    commands.insert_resource(config);
+
+    spawn_bands(galaxy.bands, &mut commands);
+    for (index, star) in galaxy.stars.into_iter().enumerate() {
+        add_star(&mut commands, star, StarId(index as u32));
     }
 }
 
-// fn generate_clusters(
-//     query: Query<&Band, Added<Band>>,
-//     mut commands: Commands,
-//     asset_server: Res<AssetServer>,
-// ) {
-//     // for band in query.iter() {
-//     //     generate_clusters_for_band(band, &mut commands, &asset_server);
-//     // }
-// }
-
-fn generate_clusters_for_band(
-    band: &Band,
-    commands: &mut Commands,
-    asset_server: &Res<AssetServer>,
-) {
-    let star_size_gen = Normal::new(STAR_SIZE_MEAN, STAR_SIZE_DEVIATION).unwrap();
-
-    for cluster_index in 0..band.cluster_count {
-        let dist = band.distance_from_center + band.size * 0.5;
-
-        let cluster_angle = (cluster_index as f32 / band.cluster_count as f32) * PI * 2.0;
-        let cluster_x = cluster_angle.sin() * dist;
-        let cluster_y = cluster_angle.cos() * dist;
-
-        let total_stars = rand::thread_rng().gen_range(MIN_STARS_IN_CLUSTER..=MAX_STARS_IN_CLUSTER);
-        for start_index in 0..total_stars {
-            let star_dist = rand::thread_rng().gen_range(0.1..=0.5) * band.size;
-            let star_angle = (start_index as f32 / total_stars as f32) * PI * 2.0;
-
-            let x = cluster_x + star_angle.sin() * star_dist;
-            let y = cluster_y + star_angle.cos() * star_dist;
-            // mean 2, standard deviation 3
-            let star_size = star_size_gen
-                .sample(&mut rand::thread_rng())
-                .clamp(0.1, 10.0);
-
-            add_star(
-                commands,
-                asset_server,
-                NewStar {
-                    x,
-                    y,
-                    size: star_size,
-                },
-            );
-        }
+fn spawn_bands(bands: Vec<Band>, commands: &mut Commands) {
+    for (index, band) in bands.into_iter().enumerate() {
+        commands
+            .spawn(SpatialBundle::from_transform(Transform::from_xyz(
+                0.0,
+                0.0,
+                BAND_Z_INDEX_START - (index as f32) * 2.0,
+            )))
+            .insert(band)
+            .insert(MatchEntity);
     }
 }
 
-fn add_star(commands: &mut Commands, asset_server: &Res<AssetServer>, star: NewStar) {
-    let size = Vec2::new(10.0 * star.size.sqrt(), 10.0 * star.size.sqrt());
-    commands
-        .spawn(SpriteBundle {
-            texture: asset_server.load("star_large.png"),
-            transform: Transform::from_xyz(star.x, star.y, 0.0),
-            sprite: Sprite {
-                custom_size: Some(size),
-                ..default()
-            },
-            ..default()
-        })
+pub fn star_sprite_size(size: f32) -> f32 {
+    10.0 * size.sqrt()
+}
+
+/// Spawns the star without any visuals, those are added by `galaxy_rendering`.
+fn add_star(commands: &mut Commands, star: NewStar, star_id: StarId) {
+    let size = Vec2::splat(star_sprite_size(star.size));
+    let mut entity = commands.spawn(SpatialBundle::from_transform(Transform::from_xyz(
+        star.x, star.y, 0.0,
+    )));
+    entity
         .insert(Star { size: star.size })
+        .insert(star_id)
         .insert(Selectable {
             width: size.x,
             height: size.y,
-        });
+        })
+        .insert(MatchEntity);
+
+    if let Some(start_slot) = star.start_slot {
+        entity.insert(StartSlot(start_slot));
+    }
 }
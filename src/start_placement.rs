@@ -0,0 +1,132 @@
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+const START_STAR_SIZE: RangeInclusive<f32> = 1.5..=4.0;
+const NEARBY_RESOURCES_RADIUS: f32 = 400.0;
+const START_PLACEMENT_ATTEMPTS: usize = 32;
+
+pub struct StartCandidate {
+    pub star: Entity,
+    pub position: Vec2,
+    pub size: f32,
+    pub start_slot: Option<u32>,
+}
+
+/// Picks a distinct start star for each player.
+///
+/// Start slots of a loaded map are used first. The rest are picked from similarly sized stars,
+/// spreading players as far from each other as possible while keeping the resources around
+/// each start balanced.
+pub fn choose_start_stars(
+    candidates: &[StartCandidate],
+    player_count: usize,
+    rng: &mut impl Rng,
+) -> Vec<Entity> {
+    let fixed: Vec<usize> = (0..player_count as u32)
+        .map_while(|slot| {
+            candidates
+                .iter()
+                .position(|candidate| candidate.start_slot == Some(slot))
+        })
+        .collect();
+
+    let free: Vec<usize> = (0..candidates.len())
+        .filter(|index| !fixed.contains(index))
+        .collect();
+    let mut pool: Vec<usize> = free
+        .iter()
+        .copied()
+        .filter(|&index| START_STAR_SIZE.contains(&candidates[index].size))
+        .collect();
+    if pool.len() + fixed.len() < player_count {
+        pool = free;
+    }
+
+    let nearby_resources: Vec<f32> = candidates
+        .iter()
+        .map(|candidate| {
+            candidates
+                .iter()
+                .filter(|other| {
+                    candidate.position.distance(other.position) < NEARBY_RESOURCES_RADIUS
+                })
+                .map(|other| other.size)
+                .sum()
+        })
+        .collect();
+
+    let min_distance_to = |index: usize, chosen: &[usize]| {
+        chosen
+            .iter()
+            .map(|&other| {
+                candidates[index]
+                    .position
+                    .distance(candidates[other].position)
+            })
+            .fold(f32::MAX, f32::min)
+    };
+
+    let mut best: Option<(f32, Vec<usize>)> = None;
+    for _ in 0..START_PLACEMENT_ATTEMPTS {
+        let mut chosen = fixed.clone();
+        if chosen.is_empty() && !pool.is_empty() {
+            chosen.push(pool[rng.gen_range(0..pool.len())]);
+        }
+
+        while chosen.len() < player_count {
+            let next = pool
+                .iter()
+                .copied()
+                .filter(|index| !chosen.contains(index))
+                .max_by(|&a, &b| {
+                    min_distance_to(a, &chosen).total_cmp(&min_distance_to(b, &chosen))
+                });
+            match next {
+                Some(next) => chosen.push(next),
+                None => break,
+            }
+        }
+
+        let min_distance = chosen
+            .iter()
+            .enumerate()
+            .map(|(i, &index)| min_distance_to(index, &chosen[..i]))
+            .fold(f32::MAX, f32::min);
+        let resources = chosen.iter().map(|&index| nearby_resources[index]);
+        let most = resources.clone().fold(0.0, f32::max);
+        let least = resources.fold(f32::MAX, f32::min);
+        let imbalance = if most > 0.0 {
+            (most - least) / most
+        } else {
+            0.0
+        };
+        let score = min_distance * (1.0 - imbalance);
+
+        if best
+            .as_ref()
+            .map_or(true, |(best_score, _)| score > *best_score)
+        {
+            best = Some((score, chosen));
+        }
+
+        // Without a random first pick every attempt is the same
+        if !fixed.is_empty() {
+            break;
+        }
+    }
+
+    let chosen = best.map(|(_, chosen)| chosen).unwrap_or_default();
+    if chosen.len() < player_count {
+        warn!(
+            "Only {} start stars for {} players",
+            chosen.len(),
+            player_count
+        );
+    }
+    chosen
+        .into_iter()
+        .map(|index| candidates[index].star)
+        .collect()
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+
+/// Smallest defence a target is weighed with when splitting ships, so undefended targets get
+/// ships too.
+const MIN_TARGET_WEIGHT: f32 = 1.0;
+
+/// How ships sent from several selected stars are spread over several target stars.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum TargetingMode {
+    /// Each star sends its ships to the target nearest to it.
+    #[default]
+    Nearest,
+    /// All stars send their ships to the single target closest to the group.
+    Concentrate,
+    /// Each star splits its ships over the targets in proportion to their defence, counting at
+    /// least one defender per target.
+    Proportional,
+}
+
+impl TargetingMode {
+    pub fn next(self) -> Self {
+        match self {
+            TargetingMode::Nearest => TargetingMode::Concentrate,
+            TargetingMode::Concentrate => TargetingMode::Proportional,
+            TargetingMode::Proportional => TargetingMode::Nearest,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            TargetingMode::Nearest => "nearest",
+            TargetingMode::Concentrate => "concentrate",
+            TargetingMode::Proportional => "by defence",
+        }
+    }
+}
+
+pub struct OrderSource {
+    pub star: Entity,
+    pub position: Vec2,
+    /// Ships the star sends.
+    pub ships: f32,
+}
+
+pub struct OrderTarget {
+    pub star: Entity,
+    pub position: Vec2,
+    pub defence: f32,
+}
+
+/// Ships sent from one star to another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dispatch {
+    pub from: Entity,
+    pub to: Entity,
+    pub ships: f32,
+}
+
+/// Assigns the ships of each source to the targets. Stars are never sent ships from themselves
+/// and equally good targets are picked in the order they are given.
+pub fn assign_targets(
+    mode: TargetingMode,
+    sources: &[OrderSource],
+    targets: &[OrderTarget],
+) -> Vec<Dispatch> {
+    match mode {
+        TargetingMode::Nearest => sources
+            .iter()
+            .filter_map(|source| {
+                let other_targets = targets.iter().filter(|target| target.star != source.star);
+                let target = nearest(source.position, other_targets)?;
+                Some(Dispatch {
+                    from: source.star,
+                    to: target.star,
+                    ships: source.ships,
+                })
+            })
+            .collect(),
+        TargetingMode::Concentrate => {
+            let Some(center) = centroid(sources) else {
+                return Vec::new();
+            };
+            let Some(target) = nearest(center, targets.iter()) else {
+                return Vec::new();
+            };
+            sources
+                .iter()
+                .filter(|source| source.star != target.star)
+                .map(|source| Dispatch {
+                    from: source.star,
+                    to: target.star,
+                    ships: source.ships,
+                })
+                .collect()
+        }
+        TargetingMode::Proportional => sources
+            .iter()
+            .flat_map(|source| {
+                let other_targets: Vec<_> = targets
+                    .iter()
+                    .filter(|target| target.star != source.star)
+                    .collect();
+                let weight = |target: &OrderTarget| target.defence.max(MIN_TARGET_WEIGHT);
+                let total_weight: f32 = other_targets.iter().map(|target| weight(target)).sum();
+
+                other_targets.into_iter().map(move |target| Dispatch {
+                    from: source.star,
+                    to: target.star,
+                    ships: source.ships * weight(target) / total_weight,
+                })
+            })
+            .collect(),
+    }
+}
+
+/// The first of the targets closest to `position`.
+fn nearest<'a>(
+    position: Vec2,
+    targets: impl Iterator<Item = &'a OrderTarget>,
+) -> Option<&'a OrderTarget> {
+    targets.fold(None, |nearest, target| match nearest {
+        Some(nearest)
+            if nearest.position.distance_squared(position)
+                <= target.position.distance_squared(position) =>
+        {
+            Some(nearest)
+        }
+        _ => Some(target),
+    })
+}
+
+fn centroid(sources: &[OrderSource]) -> Option<Vec2> {
+    if sources.is_empty() {
+        return None;
+    }
+    let sum: Vec2 = sources.iter().map(|source| source.position).sum();
+    Some(sum / sources.len() as f32)
+}
@@ -93,7 +93,7 @@ fn key_control(
     let scroll_sensitivity = q.0.scroll_sensitivity;
     let mut transform = q.1;
 
-    let scroll_delta_base = scroll_sensitivity * transform.translation.z * time.delta_seconds();
+    let scroll_delta_base = scroll_sensitivity * transform.translation.z * time.raw_delta_seconds();
 
     if keyboard_input.pressed(KeyCode::W) || keyboard_input.pressed(KeyCode::Up) {
         transform.translation.y += scroll_delta_base * transform.scale.y;
@@ -0,0 +1,152 @@
+//! Setup shared by the integration tests.
+#![allow(dead_code)]
+
+use bevy::prelude::*;
+
+use bevy_jam_1::{
+    app_state::AppState,
+    game_command::GameCommand,
+    match_settings::MatchSettings,
+    players::{GeneratedPlayers, OwnedBy, Player, PlayerId},
+    ship::{AttachedFleet, Fleet, FlyTo},
+    simulation_clock::SimulationClock,
+    star_generation::{Star, StarId},
+    StarsIoSimulationPlugins,
+};
+
+/// Settings for a match with the given seed. Unlike the game, tests never read the arguments
+/// they are run with.
+pub fn match_settings(seed: u64) -> MatchSettings {
+    MatchSettings { seed, ..default() }
+}
+
+/// The simulation without rendering, still in the main menu.
+pub fn new_app() -> App {
+    let mut app = App::new();
+    app.insert_resource(MatchSettings::default())
+        .add_plugins(MinimalPlugins)
+        .add_plugins(StarsIoSimulationPlugins)
+        .init_resource::<Input<KeyCode>>();
+    app
+}
+
+/// Starts a match from the setup screen, which generates the galaxy and the players.
+pub fn start_match(settings: MatchSettings) -> App {
+    let mut app = new_app();
+    app.insert_resource(settings);
+    for state in [AppState::MatchSetup, AppState::Playing] {
+        app.world.resource_mut::<NextState<AppState>>().set(state);
+        app.update();
+    }
+    app
+}
+
+/// A running match without any generated galaxy or players.
+pub fn empty_match() -> App {
+    let mut app = new_app();
+    app.world.resource_mut::<GeneratedPlayers>().generated = true;
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Playing);
+    app.update();
+    app
+}
+
+pub fn tick(app: &App) -> u64 {
+    app.world.resource::<SimulationClock>().tick()
+}
+
+pub fn run_ticks(app: &mut App, ticks: u64) {
+    for _ in 0..ticks {
+        app.world.run_schedule(FixedUpdate);
+    }
+}
+
+pub fn run_until(app: &mut App, until: u64) {
+    while tick(app) < until {
+        app.world.run_schedule(FixedUpdate);
+    }
+}
+
+pub fn spawn_player(app: &mut App, name: &str, is_human: bool) -> Entity {
+    let player_id = PlayerId(app.world.query::<&Player>().iter(&app.world).count() as u32);
+    app.world
+        .spawn((
+            Player {
+                name: name.to_string(),
+                is_human,
+                color: Color::WHITE,
+            },
+            player_id,
+        ))
+        .id()
+}
+
+pub fn spawn_star(app: &mut App, position: Vec2, size: f32) -> Entity {
+    let star_id = StarId(app.world.query::<&Star>().iter(&app.world).count() as u32);
+    app.world
+        .spawn((
+            SpatialBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+            Star { size },
+            star_id,
+        ))
+        .id()
+}
+
+/// Gives the star to the player, defended by a fleet of the given size.
+pub fn own_star(app: &mut App, star: Entity, player: Entity, fleet_size: f32) -> Entity {
+    let fleet = app
+        .world
+        .spawn((
+            SpatialBundle::default(),
+            Fleet {
+                player,
+                size: fleet_size,
+            },
+        ))
+        .id();
+    app.world
+        .entity_mut(star)
+        .insert((OwnedBy { player }, AttachedFleet { fleet_id: fleet }))
+        .add_child(fleet);
+    fleet
+}
+
+/// Launches a fleet directly, without going through a command.
+pub fn send_fleet(app: &mut App, player: Entity, from: Entity, to: Entity, size: f32) -> Entity {
+    app.world
+        .spawn((
+            Fleet { player, size },
+            FlyTo {
+                origin_star: from,
+                destination_star: to,
+            },
+        ))
+        .id()
+}
+
+pub fn send_command(app: &mut App, command: GameCommand) {
+    app.world
+        .resource_mut::<Events<GameCommand>>()
+        .send(command);
+}
+
+pub fn owner(app: &App, star: Entity) -> Option<Entity> {
+    app.world
+        .get::<OwnedBy>(star)
+        .map(|owned_by| owned_by.player)
+}
+
+pub fn attached_fleet(app: &App, star: Entity) -> &Fleet {
+    let attached_fleet = app.world.get::<AttachedFleet>(star).unwrap();
+    app.world.get::<Fleet>(attached_fleet.fleet_id).unwrap()
+}
+
+/// Player, size and destination of every fleet in flight.
+pub fn flying_fleets(app: &mut App) -> Vec<(Entity, f32, Entity)> {
+    app.world
+        .query::<(&Fleet, &FlyTo)>()
+        .iter(&app.world)
+        .map(|(fleet, fly_to)| (fleet.player, fleet.size, fly_to.destination_star))
+        .collect()
+}
@@ -0,0 +1,31 @@
+use bevy_jam_1::{
+    match_settings::{MatchSettings, MAX_AI_PLAYERS},
+    palette::PaletteKind,
+};
+
+fn settings_with_players(palette: PaletteKind, player_count: usize) -> MatchSettings {
+    let mut settings = MatchSettings::default();
+    settings.set_palette(palette);
+    while settings.players.len() < player_count {
+        settings.add_ai();
+    }
+    settings.players.truncate(player_count);
+    settings
+}
+
+#[test]
+fn markers_are_shown_when_colours_repeat() {
+    let settings = settings_with_players(PaletteKind::ColorBlindSafe, 11);
+
+    assert!(!settings.show_markers);
+    assert!(settings.colors_repeat());
+    assert!(settings.markers_shown());
+}
+
+#[test]
+fn distinct_colours_last_for_every_player() {
+    let settings = settings_with_players(PaletteKind::Distinct, MAX_AI_PLAYERS + 1);
+
+    assert!(!settings.colors_repeat());
+    assert!(!settings.markers_shown());
+}
@@ -0,0 +1,187 @@
+use bevy::{ecs::system::Command, prelude::*};
+
+use bevy_jam_1::{
+    app_state::AppState,
+    galaxy_config::GalaxyPreset,
+    galaxy_map::GalaxyMap,
+    match_settings::{MatchSettings, PlayerSettings},
+    players::{OwnedBy, PlayerId},
+    replay::{Replay, ReplayPlayback, WatchReplay},
+    ship::{AttachedFleet, Fleet, FleetId},
+    simulation_clock::{SimulationClock, TICKS_PER_SECOND},
+    star_generation::StarId,
+};
+
+mod common;
+
+use common::{match_settings, new_app, run_until, start_match, tick};
+
+const SEED: u64 = 7;
+/// The AI acts every 5 seconds, so a few of its orders are recorded.
+const MATCH_TICKS: u64 = 12 * TICKS_PER_SECOND;
+
+/// Starts an AI-only match on a small galaxy from the setup screen.
+fn ai_match() -> App {
+    let mut settings = match_settings(SEED);
+    settings.set_preset(GalaxyPreset::SmallDuel);
+    settings.players = settings
+        .palette
+        .colors(2)
+        .into_iter()
+        .enumerate()
+        .map(|(index, color)| PlayerSettings::ai(index, color))
+        .collect();
+    start_match(settings)
+}
+
+/// The replay of the match so far.
+fn recorded_replay(app: &App) -> Replay {
+    let mut replay = app.world.resource::<Replay>().clone();
+    replay.ticks = tick(app);
+    replay
+}
+
+/// Starts watching the replay, paused so only seeking advances it.
+fn watch(replay: Replay) -> App {
+    let mut app = new_app();
+    WatchReplay(replay).apply(&mut app.world);
+    app.world
+        .resource_mut::<NextState<AppState>>()
+        .set(AppState::Playing);
+    app.update();
+    app.world.resource_mut::<SimulationClock>().toggle_pause();
+    app
+}
+
+fn seek(app: &mut App, tick: u64) {
+    app.world.resource_mut::<ReplayPlayback>().seek(tick);
+    for _ in 0..10 {
+        app.update();
+        if !app.world.resource::<ReplayPlayback>().seeking() {
+            return;
+        }
+    }
+    panic!("Seeking to tick {tick} didn't finish");
+}
+
+#[derive(Debug, PartialEq)]
+struct Snapshot {
+    /// Owner and defending fleet of each star.
+    stars: Vec<(StarId, Option<PlayerId>, f32)>,
+    fleets: Vec<(FleetId, f32, Vec3)>,
+}
+
+fn snapshot(app: &mut App) -> Snapshot {
+    let world = &mut app.world;
+    let mut stars: Vec<_> = world
+        .query::<(&StarId, Option<&OwnedBy>, Option<&AttachedFleet>)>()
+        .iter(world)
+        .map(|(&star_id, owned_by, attached_fleet)| {
+            let owner = owned_by.and_then(|owned_by| world.get::<PlayerId>(owned_by.player));
+            let defenders = attached_fleet
+                .and_then(|attached_fleet| world.get::<Fleet>(attached_fleet.fleet_id))
+                .map_or(0.0, |fleet| fleet.size);
+            (star_id, owner.copied(), defenders)
+        })
+        .collect();
+    stars.sort_by_key(|&(star_id, ..)| star_id);
+
+    let mut fleets: Vec<_> = world
+        .query::<(&FleetId, &Fleet, &Transform)>()
+        .iter(world)
+        .map(|(&fleet_id, fleet, transform)| (fleet_id, fleet.size, transform.translation))
+        .collect();
+    fleets.sort_by_key(|&(fleet_id, ..)| fleet_id);
+
+    Snapshot { stars, fleets }
+}
+
+#[test]
+fn match_is_recorded_with_the_tick_of_each_command() {
+    let mut app = ai_match();
+    run_until(&mut app, MATCH_TICKS);
+
+    let replay = recorded_replay(&app);
+    assert_eq!(replay.seed, SEED);
+    assert_eq!(replay.players.len(), 2);
+    assert!(!replay.commands.is_empty());
+    // Orders given by the AI every 5 seconds are applied the tick after
+    for recorded in replay.commands.iter() {
+        assert_eq!(recorded.tick % (5 * TICKS_PER_SECOND), 1, "{recorded:?}");
+    }
+}
+
+#[test]
+fn replay_survives_saving_and_loading() {
+    let mut app = ai_match();
+    run_until(&mut app, MATCH_TICKS);
+    let replay = recorded_replay(&app);
+
+    let path = std::env::temp_dir().join(format!("stars_io_replay_{}.ron", std::process::id()));
+    replay.save(&path).unwrap();
+    let loaded = Replay::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(loaded.seed, replay.seed);
+    assert_eq!(loaded.ticks, replay.ticks);
+    assert_eq!(loaded.commands, replay.commands);
+    assert_eq!(loaded.galaxy.band_count, replay.galaxy.band_count);
+}
+
+#[test]
+fn playback_re_simulates_the_match() {
+    let mut app = ai_match();
+    run_until(&mut app, MATCH_TICKS / 2);
+    let halfway = snapshot(&mut app);
+    run_until(&mut app, MATCH_TICKS);
+    let end = snapshot(&mut app);
+
+    let mut playback = watch(recorded_replay(&app));
+
+    seek(&mut playback, MATCH_TICKS);
+    assert_eq!(tick(&playback), MATCH_TICKS);
+    assert_eq!(snapshot(&mut playback), end);
+
+    // Seeking back plays the match again from the start
+    seek(&mut playback, MATCH_TICKS / 2);
+    assert_eq!(tick(&playback), MATCH_TICKS / 2);
+    assert_eq!(snapshot(&mut playback), halfway);
+    assert!(playback.world.resource::<SimulationClock>().paused());
+}
+
+#[test]
+fn seeking_stops_at_the_end_of_the_replay() {
+    let mut app = ai_match();
+    run_until(&mut app, MATCH_TICKS);
+
+    let mut playback = watch(recorded_replay(&app));
+    seek(&mut playback, 10 * MATCH_TICKS);
+
+    assert_eq!(tick(&playback), MATCH_TICKS);
+}
+
+#[test]
+fn stopping_playback_restores_the_match_settings() {
+    let mut app = ai_match();
+    run_until(&mut app, MATCH_TICKS);
+    let replay = recorded_replay(&app);
+
+    let mut playback = new_app();
+    playback.world.resource_mut::<MatchSettings>().seed = SEED + 1;
+    playback.insert_resource(GalaxyMap::default());
+    WatchReplay(replay).apply(&mut playback.world);
+    assert_eq!(playback.world.resource::<MatchSettings>().seed, SEED);
+    assert!(!playback.world.contains_resource::<GalaxyMap>());
+
+    for state in [AppState::Playing, AppState::Paused, AppState::MainMenu] {
+        playback
+            .world
+            .resource_mut::<NextState<AppState>>()
+            .set(state);
+        playback.update();
+    }
+
+    assert!(!playback.world.contains_resource::<ReplayPlayback>());
+    assert_eq!(playback.world.resource::<MatchSettings>().seed, SEED + 1);
+    assert!(playback.world.contains_resource::<GalaxyMap>());
+}
@@ -0,0 +1,934 @@
+use bevy::{ecs::system::SystemState, prelude::*};
+
+use bevy_jam_1::{
+    app_state::{AppState, MatchResult},
+    game_command::GameCommand,
+    match_settings::Difficulty,
+    players::{OwnedBy, PlayerId},
+    selection::{OnSelected, Selectable},
+    selection_ui::Selected,
+    ship::{
+        Battle, CombatResolved, CombatRules, Fleet, FleetArrival, FleetId, FleetSpeed,
+        FleetSpeedRules, FlyTo, RallyPoint,
+    },
+    simulation_clock::TICKS_PER_SECOND,
+    star_generation::StarId,
+};
+
+mod common;
+
+use common::{
+    attached_fleet, empty_match, flying_fleets, own_star, owner, run_ticks, send_command,
+    send_fleet, spawn_player, spawn_star,
+};
+
+#[test]
+fn fleet_arrives_and_reinforces_own_star() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let destination = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 0.0);
+    own_star(&mut app, destination, player, 2.0);
+    let fleet = send_fleet(&mut app, player, origin, destination, 5.0);
+
+    // 100 units at 100 units per second, plus a tick to place the fleet
+    run_ticks(&mut app, TICKS_PER_SECOND + 2);
+
+    assert!(app.world.get_entity(fleet).is_none());
+    assert_eq!(owner(&app, destination), Some(player));
+    assert!(attached_fleet(&app, destination).size >= 7.0);
+}
+
+#[test]
+fn fleet_in_flight_moves_towards_destination() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let destination = spawn_star(&mut app, Vec2::new(1000.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 0.0);
+    let fleet = send_fleet(&mut app, player, origin, destination, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND);
+
+    let position = app.world.get::<Transform>(fleet).unwrap().translation;
+    assert!(position.x > 90.0 && position.x < 110.0, "{position}");
+    assert_eq!(position.y, 0.0);
+}
+
+#[test]
+fn fight_transfers_ownership_to_stronger_attacker() {
+    let mut app = empty_match();
+    let attacker = spawn_player(&mut app, "Human", true);
+    let defender = spawn_player(&mut app, "AI", false);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, attacker, 0.0);
+    own_star(&mut app, target, defender, 3.0);
+    send_fleet(&mut app, attacker, origin, target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 2);
+
+    assert_eq!(owner(&app, target), Some(attacker));
+    let fleet = attached_fleet(&app, target);
+    assert_eq!(fleet.player, attacker);
+    // 5 attacking ships against 3 defenders and what the star produced meanwhile
+    assert!(fleet.size > 1.5 && fleet.size < 2.0, "{}", fleet.size);
+}
+
+#[test]
+fn fight_keeps_ownership_with_stronger_defender() {
+    let mut app = empty_match();
+    let attacker = spawn_player(&mut app, "Human", true);
+    let defender = spawn_player(&mut app, "AI", false);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, attacker, 0.0);
+    own_star(&mut app, target, defender, 8.0);
+    send_fleet(&mut app, attacker, origin, target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 2);
+
+    assert_eq!(owner(&app, target), Some(defender));
+    let fleet = attached_fleet(&app, target);
+    assert_eq!(fleet.player, defender);
+    assert!(fleet.size > 3.0 && fleet.size < 3.5, "{}", fleet.size);
+}
+
+#[test]
+fn fleet_captures_unowned_star() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 0.0);
+    send_fleet(&mut app, player, origin, target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 2);
+
+    assert_eq!(owner(&app, target), Some(player));
+}
+
+#[test]
+fn production_increases_fleet_size() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let star = spawn_star(&mut app, Vec2::ZERO, 2.0);
+    app.world.entity_mut(star).insert(OwnedBy { player });
+
+    run_ticks(&mut app, 1);
+    assert_eq!(attached_fleet(&app, star).size, 0.0);
+
+    // Stars produce a tenth of their size twice per second
+    run_ticks(&mut app, 2 * TICKS_PER_SECOND);
+    let size = attached_fleet(&app, star).size;
+    assert!((size - 0.8).abs() < 1e-4, "{size}");
+}
+
+#[test]
+fn owning_most_stars_is_victory() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    for index in 0..5 {
+        let star = spawn_star(&mut app, Vec2::new(index as f32 * 100.0, 0.0), 1.0);
+        own_star(&mut app, star, human, 1.0);
+    }
+    let ai_star = spawn_star(&mut app, Vec2::new(0.0, 1000.0), 1.0);
+    own_star(&mut app, ai_star, ai, 1.0);
+
+    run_ticks(&mut app, 1);
+    app.update();
+
+    assert_eq!(
+        app.world.get_resource::<MatchResult>(),
+        Some(&MatchResult::Victory)
+    );
+    assert_eq!(
+        app.world.resource::<State<AppState>>().get(),
+        &AppState::GameOver
+    );
+}
+
+#[test]
+fn losing_all_stars_is_defeat() {
+    let mut app = empty_match();
+    spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    let ai_star = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    own_star(&mut app, ai_star, ai, 1.0);
+
+    run_ticks(&mut app, 1);
+    app.update();
+
+    assert_eq!(
+        app.world.get_resource::<MatchResult>(),
+        Some(&MatchResult::Defeat)
+    );
+    assert_eq!(
+        app.world.resource::<State<AppState>>().get(),
+        &AppState::GameOver
+    );
+}
+
+#[test]
+fn undecided_match_keeps_playing() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    let human_star = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    own_star(&mut app, human_star, human, 1.0);
+    let ai_star = spawn_star(&mut app, Vec2::new(0.0, 1000.0), 1.0);
+    own_star(&mut app, ai_star, ai, 1.0);
+
+    run_ticks(&mut app, 1);
+    app.update();
+
+    assert!(app.world.get_resource::<MatchResult>().is_none());
+    assert_eq!(
+        app.world.resource::<State<AppState>>().get(),
+        &AppState::Playing
+    );
+}
+
+#[test]
+fn fleet_returns_home_when_destination_is_despawned() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let destination = spawn_star(&mut app, Vec2::new(1000.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 0.0);
+    let fleet = send_fleet(&mut app, player, origin, destination, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND / 2);
+    app.world.despawn(destination);
+    run_ticks(&mut app, 1);
+
+    assert_eq!(
+        app.world.get::<FlyTo>(fleet).unwrap().destination_star,
+        origin
+    );
+
+    run_ticks(&mut app, TICKS_PER_SECOND);
+
+    assert!(app.world.get_entity(fleet).is_none());
+    assert!(attached_fleet(&app, origin).size >= 5.0);
+}
+
+#[test]
+fn fleet_stays_put_when_origin_and_destination_are_despawned() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let destination = spawn_star(&mut app, Vec2::new(1000.0, 0.0), 1.0);
+    let fleet = send_fleet(&mut app, player, origin, destination, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND / 2);
+    app.world.despawn(origin);
+    app.world.despawn(destination);
+    run_ticks(&mut app, 1);
+    let position = app.world.get::<Transform>(fleet).unwrap().translation;
+    run_ticks(&mut app, TICKS_PER_SECOND);
+
+    assert!(app.world.get::<FlyTo>(fleet).is_none());
+    assert_eq!(
+        app.world.get::<Transform>(fleet).unwrap().translation,
+        position
+    );
+}
+
+#[test]
+fn fleet_captures_star_whose_fleet_is_gone() {
+    let mut app = empty_match();
+    let attacker = spawn_player(&mut app, "Human", true);
+    let defender = spawn_player(&mut app, "AI", false);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, attacker, 0.0);
+    let target_fleet = own_star(&mut app, target, defender, 3.0);
+    send_fleet(&mut app, attacker, origin, target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND / 2);
+    app.world.despawn(target_fleet);
+    run_ticks(&mut app, TICKS_PER_SECOND);
+
+    assert_eq!(owner(&app, target), Some(attacker));
+    assert_eq!(attached_fleet(&app, target).player, attacker);
+}
+
+#[test]
+fn reinforcements_stay_at_own_star_whose_fleet_is_gone() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 0.0);
+    let target_fleet = own_star(&mut app, target, player, 3.0);
+    send_fleet(&mut app, player, origin, target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND / 2);
+    app.world.despawn(target_fleet);
+    run_ticks(&mut app, TICKS_PER_SECOND);
+
+    assert_eq!(owner(&app, target), Some(player));
+    assert!(attached_fleet(&app, target).size >= 5.0);
+    assert!(flying_fleets(&mut app).is_empty());
+}
+
+#[test]
+fn fleet_stays_at_the_unowned_star_it_captured() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 0.0);
+    send_fleet(&mut app, player, origin, target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 2);
+
+    assert_eq!(attached_fleet(&app, target).player, player);
+    assert!(attached_fleet(&app, target).size >= 5.0);
+}
+
+#[test]
+fn opposing_fleets_fight_in_flight() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    let human_star = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let ai_star = spawn_star(&mut app, Vec2::new(300.0, 0.0), 1.0);
+    own_star(&mut app, human_star, human, 0.0);
+    own_star(&mut app, ai_star, ai, 0.0);
+    let human_fleet = send_fleet(&mut app, human, human_star, ai_star, 5.0);
+    let ai_fleet = send_fleet(&mut app, ai, ai_star, human_star, 3.0);
+
+    // The fleets meet half way after 1.5 seconds
+    run_ticks(&mut app, 100);
+
+    assert!(app.world.get_entity(ai_fleet).is_none());
+    assert_eq!(app.world.get::<Fleet>(human_fleet).unwrap().size, 2.0);
+    assert_eq!(
+        app.world
+            .get::<FlyTo>(human_fleet)
+            .unwrap()
+            .destination_star,
+        ai_star
+    );
+    let position = app.world.get::<Transform>(human_fleet).unwrap().translation;
+    assert!(position.x > 150.0 && position.x < 300.0, "{position}");
+    assert_eq!(owner(&app, human_star), Some(human));
+}
+
+#[test]
+fn own_fleets_pass_each_other_in_flight() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let first_star = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let second_star = spawn_star(&mut app, Vec2::new(300.0, 0.0), 1.0);
+    own_star(&mut app, first_star, player, 0.0);
+    own_star(&mut app, second_star, player, 0.0);
+    let first_fleet = send_fleet(&mut app, player, first_star, second_star, 5.0);
+    let second_fleet = send_fleet(&mut app, player, second_star, first_star, 3.0);
+
+    run_ticks(&mut app, 100);
+
+    assert_eq!(app.world.get::<Fleet>(first_fleet).unwrap().size, 5.0);
+    assert_eq!(app.world.get::<Fleet>(second_fleet).unwrap().size, 3.0);
+}
+
+#[test]
+fn defender_bonus_scales_with_star_size() {
+    let mut app = empty_match();
+    app.insert_resource(CombatRules {
+        defender_bonus: 1.0,
+        battle_rate: None,
+    });
+    let attacker = spawn_player(&mut app, "Human", true);
+    let defender = spawn_player(&mut app, "AI", false);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, attacker, 0.0);
+    own_star(&mut app, target, defender, 3.0);
+    send_fleet(&mut app, attacker, origin, target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 2);
+
+    // Each of the 3.2 defenders counts twice, so 5 attackers only destroy 2.5 of them
+    assert_eq!(owner(&app, target), Some(defender));
+    let size = attached_fleet(&app, target).size;
+    assert!((size - 0.7).abs() < 1e-4, "{size}");
+}
+
+#[test]
+fn combat_resolved_reports_losses_and_capture() {
+    let mut app = empty_match();
+    app.insert_resource(CombatRules {
+        defender_bonus: 0.0,
+        battle_rate: None,
+    });
+    let attacker = spawn_player(&mut app, "Human", true);
+    let defender = spawn_player(&mut app, "AI", false);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, attacker, 0.0);
+    own_star(&mut app, target, defender, 3.0);
+    send_fleet(&mut app, attacker, origin, target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 2);
+
+    let events = app.world.resource::<Events<CombatResolved>>();
+    let resolved: Vec<_> = events.iter_current_update_events().collect();
+    assert_eq!(resolved.len(), 1);
+    let resolved = resolved[0];
+    assert_eq!(resolved.star, target);
+    assert_eq!(resolved.attacker, attacker);
+    assert_eq!(resolved.defender, Some(defender));
+    assert!(resolved.ownership_changed);
+    assert!((resolved.attacker_losses - 3.2).abs() < 1e-4);
+    assert!((resolved.defender_losses - 3.2).abs() < 1e-4);
+}
+
+#[test]
+fn gradual_battle_plays_out_over_several_ticks() {
+    let mut app = empty_match();
+    app.insert_resource(CombatRules {
+        defender_bonus: 0.0,
+        battle_rate: Some(1.0),
+    });
+    let attacker = spawn_player(&mut app, "Human", true);
+    let defender = spawn_player(&mut app, "AI", false);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, attacker, 0.0);
+    own_star(&mut app, target, defender, 1.0);
+    let fleet = send_fleet(&mut app, attacker, origin, target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 2 + TICKS_PER_SECOND / 2);
+
+    // A ship per second is lost on both sides while the star keeps producing
+    assert_eq!(owner(&app, target), Some(defender));
+    assert!(app.world.get::<Battle>(fleet).is_some());
+    let size = app.world.get::<Fleet>(fleet).unwrap().size;
+    assert!(size < 5.0 && size > 4.0, "{size}");
+
+    run_ticks(&mut app, 2 * TICKS_PER_SECOND);
+
+    assert_eq!(owner(&app, target), Some(attacker));
+    assert!(app.world.get_entity(fleet).is_none());
+}
+
+#[test]
+fn fleets_fighting_at_a_star_are_not_intercepted() {
+    let mut app = empty_match();
+    app.insert_resource(CombatRules {
+        defender_bonus: 0.0,
+        battle_rate: Some(1.0),
+    });
+    let attacker = spawn_player(&mut app, "Human", true);
+    let defender = spawn_player(&mut app, "AI", false);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, attacker, 0.0);
+    own_star(&mut app, target, defender, 1.0);
+    let fleet = send_fleet(&mut app, attacker, origin, target, 5.0);
+    run_ticks(&mut app, TICKS_PER_SECOND + 5);
+    assert!(app.world.get::<Battle>(fleet).is_some());
+
+    // A fleet leaving the star passes the attacker fighting there
+    let leaving = send_fleet(&mut app, defender, target, origin, 3.0);
+    run_ticks(&mut app, 5);
+
+    assert_eq!(app.world.get::<Fleet>(leaving).unwrap().size, 3.0);
+    assert!(app.world.get::<Fleet>(fleet).unwrap().size > 4.5);
+}
+
+#[test]
+fn gradual_battle_reports_its_total_losses() {
+    let mut app = empty_match();
+    app.insert_resource(CombatRules {
+        defender_bonus: 0.0,
+        battle_rate: Some(1.0),
+    });
+    let attacker = spawn_player(&mut app, "Human", true);
+    let defender = spawn_player(&mut app, "AI", false);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, attacker, 0.0);
+    own_star(&mut app, target, defender, 1.0);
+    send_fleet(&mut app, attacker, origin, target, 5.0);
+
+    run_ticks(&mut app, 4 * TICKS_PER_SECOND);
+
+    let events = app.world.resource::<Events<CombatResolved>>();
+    let resolved: Vec<_> = events.iter_current_update_events().collect();
+    assert_eq!(resolved.len(), 1);
+    let resolved = resolved[0];
+    assert_eq!(resolved.star, target);
+    assert!(resolved.ownership_changed);
+    // The defenders and the ships the star produced during the battle, a ship for a ship
+    let losses = resolved.defender_losses;
+    assert!(losses > 1.0 && losses < 2.0, "{losses}");
+    assert!((resolved.attacker_losses - losses).abs() < 1e-4);
+}
+
+#[test]
+fn small_fleets_fly_faster_than_big_ones() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let destination = spawn_star(&mut app, Vec2::new(1000.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 0.0);
+    let raid = send_fleet(&mut app, player, origin, destination, 2.0);
+    let armada = send_fleet(&mut app, player, origin, destination, 50.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND);
+
+    let rules = app.world.resource::<FleetSpeedRules>();
+    assert_eq!(
+        app.world.get::<FleetSpeed>(raid).unwrap().0,
+        rules.speed(2.0)
+    );
+    assert_eq!(
+        app.world.get::<FleetSpeed>(armada).unwrap().0,
+        rules.speed(50.0)
+    );
+    let raid_x = app.world.get::<Transform>(raid).unwrap().translation.x;
+    let armada_x = app.world.get::<Transform>(armada).unwrap().translation.x;
+    assert!(raid_x > armada_x, "{raid_x} {armada_x}");
+}
+
+#[test]
+fn arrival_time_counts_down_while_flying() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let destination = spawn_star(&mut app, Vec2::new(1000.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 0.0);
+    let fleet = send_fleet(&mut app, player, origin, destination, 5.0);
+    let mut fleet_arrival = SystemState::<FleetArrival>::new(&mut app.world);
+
+    // Known before the fleet is placed at its origin
+    let seconds = fleet_arrival.get(&app.world).seconds(fleet).unwrap();
+    assert!((seconds - 10.0).abs() < 1e-3, "{seconds}");
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 1);
+
+    let seconds = fleet_arrival.get(&app.world).seconds(fleet).unwrap();
+    assert!((seconds - 9.0).abs() < 1e-3, "{seconds}");
+    assert_eq!(fleet_arrival.get(&app.world).seconds(origin), None);
+}
+
+#[test]
+fn redirected_fleet_steers_from_its_current_position() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let first_target = spawn_star(&mut app, Vec2::new(1000.0, 0.0), 1.0);
+    let second_target = spawn_star(&mut app, Vec2::new(100.0, 200.0), 1.0);
+    own_star(&mut app, origin, player, 0.0);
+    let fleet = send_fleet(&mut app, player, origin, first_target, 5.0);
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 1);
+    assert!(app.world.get::<Selectable>(fleet).is_some());
+    app.world.get_mut::<FlyTo>(fleet).unwrap().destination_star = second_target;
+
+    // Straight up from where the fleet was, 200 units at 100 units per second
+    run_ticks(&mut app, TICKS_PER_SECOND);
+    let position = app.world.get::<Transform>(fleet).unwrap().translation;
+    assert!((position.x - 100.0).abs() < 1e-3, "{position}");
+    assert!((position.y - 100.0).abs() < 1e-3, "{position}");
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 1);
+    assert!(app.world.get_entity(fleet).is_none());
+    assert_eq!(owner(&app, second_target), Some(player));
+    assert_eq!(owner(&app, first_target), None);
+}
+
+#[test]
+fn ai_sends_enough_ships_to_beat_the_defence() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    app.world.entity_mut(ai).insert(Difficulty::Normal);
+    let ai_star = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let human_star = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, ai_star, ai, 20.0);
+    own_star(&mut app, human_star, human, 5.0);
+
+    // The AI acts every 5 seconds and its orders are carried out the next tick
+    run_ticks(&mut app, 5 * TICKS_PER_SECOND + 1);
+
+    let sent = flying_fleets(&mut app);
+    assert_eq!(sent.len(), 1);
+    let (player, size, destination) = sent[0];
+    assert_eq!((player, destination), (ai, human_star));
+    assert!(size > 6.0 * 1.05 && size < 10.0, "{size}");
+
+    // Big fleets are a bit slower than 100 units per second
+    run_ticks(&mut app, TICKS_PER_SECOND + 5);
+    assert_eq!(owner(&app, human_star), Some(ai));
+}
+
+#[test]
+fn hard_ai_holds_back_from_a_stronger_defence() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    app.world.entity_mut(ai).insert(Difficulty::Hard);
+    let ai_star = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let human_star = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, ai_star, ai, 10.0);
+    own_star(&mut app, human_star, human, 20.0);
+
+    run_ticks(&mut app, 5 * TICKS_PER_SECOND + 1);
+
+    assert!(flying_fleets(&mut app).is_empty());
+}
+
+#[test]
+fn ai_attacks_the_star_its_fleet_reaches_first() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    app.world.entity_mut(ai).insert(Difficulty::Normal);
+    let ai_star = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let fortress = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    let outpost = spawn_star(&mut app, Vec2::new(-140.0, 0.0), 1.0);
+    own_star(&mut app, ai_star, ai, 100.0);
+    own_star(&mut app, fortress, human, 40.0);
+    own_star(&mut app, outpost, human, 1.0);
+
+    run_ticks(&mut app, 5 * TICKS_PER_SECOND + 1);
+
+    // Taking the closer star needs half the fleet, which flies slower than the few ships the
+    // farther one needs
+    let sent = flying_fleets(&mut app);
+    assert_eq!(sent.len(), 1);
+    let (player, size, destination) = sent[0];
+    assert_eq!((player, destination), (ai, outpost));
+    assert!(size < 30.0, "{size}");
+}
+
+/// Selects the `sources` and right-clicks the `targets` like the human player does, then runs
+/// the tick that carries out the order.
+fn right_click(app: &mut App, sources: &[Entity], targets: Vec<Entity>) {
+    for &source in sources {
+        let marker = app.world.spawn(Selected).id();
+        app.world.entity_mut(source).add_child(marker);
+    }
+    app.world
+        .resource_mut::<Events<OnSelected>>()
+        .send(OnSelected {
+            entities: targets,
+            mouse_button: MouseButton::Right,
+        });
+    app.update();
+    run_ticks(app, 1);
+}
+
+#[test]
+fn right_click_on_own_star_reinforces_it() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let frontier = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 10.0);
+    own_star(&mut app, frontier, player, 2.0);
+
+    right_click(&mut app, &[origin], vec![frontier]);
+
+    let sent = flying_fleets(&mut app);
+    assert_eq!(sent.len(), 1);
+    assert_eq!((sent[0].0, sent[0].2), (player, frontier));
+    let size = attached_fleet(&app, origin).size;
+    assert!((size - 5.0).abs() < 0.1, "{size}");
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 2);
+    assert!(attached_fleet(&app, frontier).size >= 7.0);
+}
+
+#[test]
+fn right_click_attacks_rather_than_reinforces() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let own = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    let enemy = spawn_star(&mut app, Vec2::new(120.0, 0.0), 1.0);
+    own_star(&mut app, origin, human, 10.0);
+    own_star(&mut app, own, human, 2.0);
+    own_star(&mut app, enemy, ai, 2.0);
+
+    right_click(&mut app, &[origin], vec![own, enemy]);
+
+    let sent = flying_fleets(&mut app);
+    assert_eq!(sent.len(), 1);
+    assert_eq!((sent[0].0, sent[0].2), (human, enemy));
+}
+
+#[test]
+fn send_fleet_command_launches_ships_on_the_next_tick() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 10.0);
+
+    send_command(
+        &mut app,
+        GameCommand::SendFleet {
+            player: PlayerId(0),
+            from: StarId(0),
+            to: StarId(1),
+            amount: 4.0,
+        },
+    );
+    run_ticks(&mut app, 1);
+
+    let launched: Vec<_> = app
+        .world
+        .query::<(&Fleet, &FlyTo, &FleetId)>()
+        .iter(&app.world)
+        .map(|(fleet, fly_to, &fleet_id)| (fleet.size, fly_to.destination_star, fleet_id))
+        .collect();
+    assert_eq!(launched, vec![(4.0, target, FleetId(0))]);
+    assert_eq!(attached_fleet(&app, origin).size, 6.0);
+}
+
+#[test]
+fn send_fleet_command_sends_at_most_the_whole_fleet() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 3.0);
+
+    send_command(
+        &mut app,
+        GameCommand::SendFleet {
+            player: PlayerId(0),
+            from: StarId(0),
+            to: StarId(1),
+            amount: 50.0,
+        },
+    );
+    run_ticks(&mut app, 1);
+
+    assert_eq!(flying_fleets(&mut app), vec![(player, 3.0, target)]);
+    assert_eq!(attached_fleet(&app, origin).size, 0.0);
+}
+
+#[test]
+fn nan_amounts_and_rallies_to_enemy_stars_are_dropped() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    let human_star = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let ai_star = spawn_star(&mut app, Vec2::new(1000.0, 0.0), 1.0);
+    own_star(&mut app, human_star, human, 10.0);
+    own_star(&mut app, ai_star, ai, 10.0);
+
+    send_command(
+        &mut app,
+        GameCommand::SendFleet {
+            player: PlayerId(0),
+            from: StarId(0),
+            to: StarId(1),
+            amount: f32::NAN,
+        },
+    );
+    send_command(
+        &mut app,
+        GameCommand::SetRally {
+            player: PlayerId(0),
+            star: StarId(0),
+            rally: Some(StarId(1)),
+        },
+    );
+    run_ticks(&mut app, 1);
+
+    assert!(flying_fleets(&mut app).is_empty());
+    assert_eq!(attached_fleet(&app, human_star).size, 10.0);
+    assert!(app.world.get::<RallyPoint>(human_star).is_none());
+}
+
+#[test]
+fn commands_for_other_players_stars_and_fleets_are_dropped() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    let human_star = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let ai_star = spawn_star(&mut app, Vec2::new(1000.0, 0.0), 1.0);
+    own_star(&mut app, human_star, human, 10.0);
+    own_star(&mut app, ai_star, ai, 10.0);
+
+    send_command(
+        &mut app,
+        GameCommand::SendFleet {
+            player: PlayerId(0),
+            from: StarId(1),
+            to: StarId(0),
+            amount: 5.0,
+        },
+    );
+    send_command(
+        &mut app,
+        GameCommand::SetRally {
+            player: PlayerId(0),
+            star: StarId(1),
+            rally: Some(StarId(0)),
+        },
+    );
+    send_command(
+        &mut app,
+        GameCommand::SendFleet {
+            player: PlayerId(1),
+            from: StarId(1),
+            to: StarId(0),
+            amount: 5.0,
+        },
+    );
+    run_ticks(&mut app, 1);
+
+    assert_eq!(flying_fleets(&mut app), vec![(ai, 5.0, human_star)]);
+    assert_eq!(attached_fleet(&app, ai_star).size, 5.0);
+    assert!(app.world.get::<RallyPoint>(ai_star).is_none());
+
+    send_command(
+        &mut app,
+        GameCommand::Recall {
+            player: PlayerId(0),
+            fleet: FleetId(0),
+        },
+    );
+    run_ticks(&mut app, 1);
+
+    assert_eq!(flying_fleets(&mut app), vec![(ai, 5.0, human_star)]);
+}
+
+#[test]
+fn recall_command_sends_the_fleet_back_to_its_origin() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let origin = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let target = spawn_star(&mut app, Vec2::new(1000.0, 0.0), 1.0);
+    own_star(&mut app, origin, player, 10.0);
+
+    send_command(
+        &mut app,
+        GameCommand::SendFleet {
+            player: PlayerId(0),
+            from: StarId(0),
+            to: StarId(1),
+            amount: 5.0,
+        },
+    );
+    run_ticks(&mut app, TICKS_PER_SECOND);
+    send_command(
+        &mut app,
+        GameCommand::Recall {
+            player: PlayerId(0),
+            fleet: FleetId(0),
+        },
+    );
+    run_ticks(&mut app, 1);
+
+    assert_eq!(flying_fleets(&mut app), vec![(player, 5.0, origin)]);
+
+    run_ticks(&mut app, TICKS_PER_SECOND + 1);
+
+    assert!(flying_fleets(&mut app).is_empty());
+    assert!(attached_fleet(&app, origin).size >= 10.0);
+    assert_eq!(owner(&app, target), None);
+}
+
+#[test]
+fn rally_point_keeps_sending_produced_ships_on() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let back = spawn_star(&mut app, Vec2::ZERO, 10.0);
+    let front = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, back, player, 4.0);
+    own_star(&mut app, front, player, 0.0);
+
+    send_command(
+        &mut app,
+        GameCommand::SetRally {
+            player: PlayerId(0),
+            star: StarId(0),
+            rally: Some(StarId(1)),
+        },
+    );
+    // Rally points send ships on every two seconds
+    run_ticks(&mut app, 2 * TICKS_PER_SECOND);
+
+    let sent = flying_fleets(&mut app);
+    assert_eq!(sent.len(), 1);
+    assert_eq!((sent[0].0, sent[0].2), (player, front));
+    assert_eq!(attached_fleet(&app, back).size, 0.0);
+
+    run_ticks(&mut app, 2 * TICKS_PER_SECOND);
+    assert!(attached_fleet(&app, front).size > 4.0);
+
+    send_command(
+        &mut app,
+        GameCommand::SetRally {
+            player: PlayerId(0),
+            star: StarId(0),
+            rally: None,
+        },
+    );
+    run_ticks(&mut app, 1);
+    assert!(app.world.get::<RallyPoint>(back).is_none());
+}
+
+#[test]
+fn rally_point_is_dropped_when_the_rally_star_is_lost() {
+    let mut app = empty_match();
+    let human = spawn_player(&mut app, "Human", true);
+    let ai = spawn_player(&mut app, "AI", false);
+    let back = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let front = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, back, human, 4.0);
+    own_star(&mut app, front, human, 0.0);
+
+    send_command(
+        &mut app,
+        GameCommand::SetRally {
+            player: PlayerId(0),
+            star: StarId(0),
+            rally: Some(StarId(1)),
+        },
+    );
+    run_ticks(&mut app, 1);
+    assert!(app.world.get::<RallyPoint>(back).is_some());
+
+    app.world.entity_mut(front).insert(OwnedBy { player: ai });
+    run_ticks(&mut app, 2 * TICKS_PER_SECOND);
+
+    assert!(app.world.get::<RallyPoint>(back).is_none());
+    assert!(flying_fleets(&mut app).is_empty());
+}
+
+#[test]
+fn shift_right_click_sets_and_clears_rally_points() {
+    let mut app = empty_match();
+    let player = spawn_player(&mut app, "Human", true);
+    let back = spawn_star(&mut app, Vec2::ZERO, 1.0);
+    let front = spawn_star(&mut app, Vec2::new(100.0, 0.0), 1.0);
+    own_star(&mut app, back, player, 4.0);
+    own_star(&mut app, front, player, 0.0);
+    app.world
+        .resource_mut::<Input<KeyCode>>()
+        .press(KeyCode::ShiftLeft);
+
+    right_click(&mut app, &[back], vec![front]);
+
+    let rally_point = app.world.get::<RallyPoint>(back).unwrap();
+    assert_eq!(rally_point.star, front);
+    assert!(flying_fleets(&mut app).is_empty());
+
+    right_click(&mut app, &[], vec![back]);
+
+    assert!(app.world.get::<RallyPoint>(back).is_none());
+}
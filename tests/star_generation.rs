@@ -0,0 +1,127 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use bevy_jam_1::{
+    galaxy_config::GalaxyConfig,
+    galaxy_layout::GalaxyLayoutKind,
+    match_settings::MatchSettings,
+    players::{OwnedBy, PlayerId},
+    star_generation::{star_sprite_size, Star, StartSlot},
+};
+
+mod common;
+
+use common::{match_settings, run_ticks, start_match};
+
+const LAYOUTS: [&str; 5] = ["bands", "spiral", "elliptical", "scatter", "rotational"];
+const SEEDS: [u64; 3] = [1, 42, 1234];
+
+fn galaxy_settings(layout: GalaxyLayoutKind, seed: u64) -> MatchSettings {
+    let mut settings = match_settings(seed);
+    settings.galaxy.layout = layout;
+    settings
+}
+
+fn generate_galaxy(layout: GalaxyLayoutKind, seed: u64) -> App {
+    start_match(galaxy_settings(layout, seed))
+}
+
+#[test]
+fn star_footprints_do_not_overlap() {
+    for layout in LAYOUTS {
+        for seed in SEEDS {
+            let settings = galaxy_settings(layout.parse().unwrap(), seed);
+            let config = &settings.galaxy;
+            let mut rng = StdRng::seed_from_u64(seed);
+
+            let stars = config.layout.layout().generate(config, &mut rng).stars;
+            assert!(!stars.is_empty(), "{layout} {seed}: no stars");
+
+            // Every generated star is spawned
+            let mut app = start_match(settings.clone());
+            let spawned = app.world.query::<&Star>().iter(&app.world).count();
+            assert_eq!(spawned, stars.len(), "{layout} {seed}");
+
+            for (index, star) in stars.iter().enumerate() {
+                // The sprite is part of the footprint, next to the labels
+                let sprite = Rect::from_center_half_size(
+                    Vec2::new(star.x, star.y),
+                    Vec2::splat(star_sprite_size(star.size) * 0.5),
+                );
+                assert!(star.footprint().contains(sprite.min));
+                assert!(star.footprint().contains(sprite.max));
+
+                for other in stars[index + 1..].iter() {
+                    assert!(
+                        !star.overlaps(other),
+                        "{layout} {seed}: {star:?} overlaps {other:?}"
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn same_seed_generates_same_galaxy() {
+    let stars = |app: &mut App| {
+        let mut stars: Vec<_> = app
+            .world
+            .query::<(&Star, &Transform)>()
+            .iter(&app.world)
+            .map(|(star, transform)| (transform.translation.truncate(), star.size))
+            .collect();
+        stars.sort_by(|a, b| a.0.x.total_cmp(&b.0.x).then(a.0.y.total_cmp(&b.0.y)));
+        stars
+    };
+
+    let mut first = generate_galaxy(GalaxyLayoutKind::Bands, 42);
+    let mut second = generate_galaxy(GalaxyLayoutKind::Bands, 42);
+    assert_eq!(stars(&mut first), stars(&mut second));
+}
+
+#[test]
+fn start_slots_mark_where_each_player_started() {
+    let mut app = generate_galaxy(GalaxyLayoutKind::Bands, 42);
+    // Players are placed in the first tick
+    run_ticks(&mut app, 1);
+    let player_count = app.world.resource::<MatchSettings>().players.len();
+
+    let mut start_slots: Vec<_> = app
+        .world
+        .query::<(&StartSlot, &OwnedBy)>()
+        .iter(&app.world)
+        .map(|(start_slot, owned_by)| {
+            let player_id = app.world.get::<PlayerId>(owned_by.player).unwrap();
+            (start_slot.0, player_id.0)
+        })
+        .collect();
+    start_slots.sort();
+
+    let expected: Vec<_> = (0..player_count as u32).map(|slot| (slot, slot)).collect();
+    assert_eq!(start_slots, expected);
+}
+
+#[test]
+fn negative_star_size_deviation_fails_to_load() {
+    let config = GalaxyConfig {
+        star_size_deviation: -1.0,
+        ..default()
+    };
+    let saved = ron::to_string(&config).unwrap();
+
+    assert!(ron::from_str::<GalaxyConfig>(&saved).is_err());
+}
+
+#[test]
+fn each_band_has_its_own_cluster_density() {
+    let config = GalaxyConfig {
+        cluster_density: vec![1.0, 0.5],
+        ..default()
+    };
+
+    assert_eq!(config.cluster_count(0), 6);
+    assert_eq!(config.cluster_count(1), 6);
+    // Bands past the end keep the last density
+    assert_eq!(config.cluster_count(2), 9);
+}
@@ -0,0 +1,109 @@
+use bevy::prelude::*;
+
+use bevy_jam_1::target_assignment::{
+    assign_targets, Dispatch, OrderSource, OrderTarget, TargetingMode,
+};
+
+fn source(star: u32, x: f32, ships: f32) -> OrderSource {
+    OrderSource {
+        star: Entity::from_raw(star),
+        position: Vec2::new(x, 0.0),
+        ships,
+    }
+}
+
+fn target(star: u32, x: f32, defence: f32) -> OrderTarget {
+    OrderTarget {
+        star: Entity::from_raw(star),
+        position: Vec2::new(x, 0.0),
+        defence,
+    }
+}
+
+fn dispatch(from: u32, to: u32, ships: f32) -> Dispatch {
+    Dispatch {
+        from: Entity::from_raw(from),
+        to: Entity::from_raw(to),
+        ships,
+    }
+}
+
+#[test]
+fn nearest_pairs_each_source_with_its_closest_target() {
+    let sources = [
+        source(0, 0.0, 4.0),
+        source(1, 90.0, 6.0),
+        source(2, 200.0, 8.0),
+    ];
+    let targets = [target(10, 100.0, 1.0), target(11, 10.0, 1.0)];
+
+    let dispatches = assign_targets(TargetingMode::Nearest, &sources, &targets);
+
+    assert_eq!(
+        dispatches,
+        vec![
+            dispatch(0, 11, 4.0),
+            dispatch(1, 10, 6.0),
+            dispatch(2, 10, 8.0)
+        ]
+    );
+}
+
+#[test]
+fn concentrate_sends_everything_to_the_target_closest_to_the_group() {
+    let sources = [source(0, 0.0, 4.0), source(1, 100.0, 6.0)];
+    let targets = [
+        target(10, -50.0, 1.0),
+        target(11, 60.0, 1.0),
+        target(12, 300.0, 1.0),
+    ];
+
+    let dispatches = assign_targets(TargetingMode::Concentrate, &sources, &targets);
+
+    assert_eq!(dispatches, vec![dispatch(0, 11, 4.0), dispatch(1, 11, 6.0)]);
+}
+
+#[test]
+fn proportional_splits_ships_by_target_defence() {
+    let sources = [source(0, 0.0, 8.0)];
+    let targets = [target(10, 100.0, 1.0), target(11, 200.0, 3.0)];
+
+    let dispatches = assign_targets(TargetingMode::Proportional, &sources, &targets);
+
+    assert_eq!(dispatches, vec![dispatch(0, 10, 2.0), dispatch(0, 11, 6.0)]);
+}
+
+#[test]
+fn proportional_splits_equally_between_undefended_targets() {
+    let sources = [source(0, 0.0, 8.0)];
+    let targets = [target(10, 100.0, 0.0), target(11, 200.0, 0.0)];
+
+    let dispatches = assign_targets(TargetingMode::Proportional, &sources, &targets);
+
+    assert_eq!(dispatches, vec![dispatch(0, 10, 4.0), dispatch(0, 11, 4.0)]);
+}
+
+#[test]
+fn proportional_sends_ships_to_undefended_targets_too() {
+    let sources = [source(0, 0.0, 8.0)];
+    let targets = [target(10, 100.0, 0.0), target(11, 200.0, 3.0)];
+
+    let dispatches = assign_targets(TargetingMode::Proportional, &sources, &targets);
+
+    assert_eq!(dispatches, vec![dispatch(0, 10, 2.0), dispatch(0, 11, 6.0)]);
+}
+
+#[test]
+fn stars_never_send_ships_to_themselves() {
+    let sources = [source(0, 0.0, 4.0), source(1, 100.0, 6.0)];
+    let targets = [target(1, 100.0, 2.0)];
+
+    for mode in [
+        TargetingMode::Nearest,
+        TargetingMode::Concentrate,
+        TargetingMode::Proportional,
+    ] {
+        let dispatches = assign_targets(mode, &sources, &targets);
+        assert_eq!(dispatches, vec![dispatch(0, 1, 4.0)], "{mode:?}");
+    }
+}